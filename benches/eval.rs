@@ -0,0 +1,76 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use serde_json::{json, Map, Value as Json};
+use wasm_filter::eval::{matches, optimize};
+use wasm_filter::lexer::lex;
+use wasm_filter::parser::{parse, Search};
+
+// Run with `cargo bench --no-default-features --bench eval`, like the parsing benchmarks
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+const STATUSES: [&str; 4] = ["open", "closed", "pending", "archived"];
+
+// `records` made-up customers, the same every run
+fn dataset(records: usize) -> Vec<Map<String, Json>> {
+    // A linear congruential generator, which is random enough for spreading out values
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut next = |below: u64| {
+        state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) % below
+    };
+
+    (0..records).map(|id| {
+        let Json::Object(record) = json!({
+            "id": id,
+            "age": next(100),
+            "score": next(10_000) as f64 / 100.0,
+            "status": STATUSES[next(4) as usize],
+            "name": format!("customer number {} of the synthetic dataset", next(1_000_000))
+        }) else { unreachable!() };
+        record
+    }).collect()
+}
+
+// Filters written in an order that's slow to evaluate, with long string comparisons and clauses that rarely settle the
+// join first
+const FILTERS: [(&str, &str); 3] = [
+    ("and", r#"name != "customer number 42 of the synthetic dataset" & status != "archived" & age > 90 & id = 7"#),
+    ("or", r#"name = "customer number 42 of the synthetic dataset" | status = "closed" | score < 50 | age != 3"#),
+    ("nested", r#"(status = "open" | status = "pending") & name != "customer" & (age >= 30 ^ score > 75) & id < 10"#)
+];
+
+fn search(filter: &str) -> Search<'_> {
+    parse(lex(filter).0).unwrap()
+}
+
+fn bench_matches(c: &mut Criterion) {
+    for (name, filter) in FILTERS {
+        let search = search(filter);
+        let optimized = optimize(&search);
+
+        let mut group = c.benchmark_group(format!("matches_{}", name));
+        for records in SIZES {
+            let dataset = dataset(records);
+            group.throughput(Throughput::Elements(records as u64));
+            for (variant, search) in [("as_written", &search), ("optimized", &optimized)] {
+                group.bench_with_input(BenchmarkId::new(variant, records), &dataset, |b, dataset| {
+                    b.iter(|| dataset.iter().filter(|record| matches(black_box(search), *record)).count())
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+fn bench_optimize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("optimize");
+    for (name, filter) in FILTERS {
+        let search = search(filter);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &search, |b, search| b.iter(|| optimize(black_box(search))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_matches, bench_optimize);
+criterion_main!(benches);
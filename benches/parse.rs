@@ -0,0 +1,98 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use wasm_filter::incremental::IncrementalLexer;
+use wasm_filter::lexer::lex;
+use wasm_filter::parser::parse;
+
+// Run with `cargo bench --no-default-features` to measure with the system allocator, since the default `wee_alloc` is
+// tuned for code size rather than speed
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+// A filter with `clauses` comparisons, such as `field_0 = 0 | field_1 != "1" & field_2 < 2 ^ ...`, that cycles through
+// the join types so the parser has to deal with precedence and nesting
+fn filter(clauses: usize) -> String {
+    let mut filter = String::new();
+    for i in 0..clauses {
+        if i > 0 {
+            filter.push_str(["| ", "& ", "^ "][i % 3]);
+        }
+        if i % 2 == 0 {
+            filter.push_str(&format!("field_{} = {} ", i, i));
+        }
+        else {
+            filter.push_str(&format!("field_{} != \"{}\" ", i, i));
+        }
+    }
+    filter
+}
+
+// The same number of comparisons, all joined by `|`, so the whole filter is one long join
+fn or_chain(clauses: usize) -> String {
+    (0..clauses).map(|i| format!("field_{} = {}", i, i)).collect::<Vec<_>>().join(" | ")
+}
+
+fn bench_lex(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lex");
+    for clauses in SIZES {
+        let input = filter(clauses);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(clauses), &input, |b, input| {
+            b.iter(|| lex(black_box(input)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_parse(c: &mut Criterion) {
+    for (name, make_filter) in [("parse", filter as fn(usize) -> String), ("parse_or_chain", or_chain)] {
+        let mut group = c.benchmark_group(name);
+        for clauses in SIZES {
+            let input = make_filter(clauses);
+            group.throughput(Throughput::Elements(clauses as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(clauses), &input, |b, input| {
+                b.iter_batched(|| lex(input).0, parse, BatchSize::LargeInput)
+            });
+        }
+        group.finish();
+    }
+}
+
+// Typing one character in the middle of the filter, which is what the live editor does on every keystroke
+fn bench_edit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("edit");
+    for clauses in SIZES {
+        let input = filter(clauses);
+        let offset = input[..input.len() / 2].rfind(' ').unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(clauses), &input, |b, input| {
+            b.iter_batched(|| IncrementalLexer::new(input), |mut lexer| lexer.edit(offset, 0, "x").unwrap(), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+// Looking up 100 nodes of a parsed filter by ID, through a `NodeTable` built for the purpose and by walking the tree to
+// each one, which is what a pass without the table would have to do
+fn bench_node_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("node_lookup");
+    for clauses in SIZES {
+        let input = filter(clauses);
+        let search = parse(lex(&input).0).unwrap();
+        let ids: Vec<_> = search.nodes().map(|(id, _)| id).collect();
+        let ids: Vec<_> = ids.iter().copied().step_by(ids.len() / 100).take(100).collect();
+
+        group.bench_with_input(BenchmarkId::new("table", clauses), &ids, |b, ids| {
+            b.iter(|| {
+                let table = search.node_table();
+                ids.iter().filter_map(|id| table.get(*id)).count()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("walk", clauses), &ids, |b, ids| {
+            b.iter(|| ids.iter().filter_map(|id| search.nodes().find(|(node_id, _)| node_id == id)).count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lex, bench_parse, bench_edit, bench_node_lookup);
+criterion_main!(benches);
@@ -0,0 +1,308 @@
+// A language server for `.filter` files, speaking JSON-RPC over stdin and stdout. It reports lexing, parsing and type
+// errors as diagnostics, and provides semantic tokens, formatting, completion and hover.
+//
+// Filters are type checked against the schema given as `{"schema": ...}` in the client's initialization options, or
+// otherwise `filter-schema.json` in the workspace root, in the JSON format `Schema::from_json` reads. Without either (or
+// if the schema is invalid, which is shown to the user), only syntax is checked: no field names are completed, every
+// comparator is offered for any field, and fields hover as unknown.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics, ShowMessage};
+use lsp_types::request::{Completion, Formatting, HoverRequest, Request as _, SemanticTokensFullRequest};
+use lsp_types::*;
+use wasm_filter::completion::{self, CompletionKind};
+use wasm_filter::lexer::{BareToken, Lexer};
+use wasm_filter::schema::{self, Schema};
+use wasm_filter::types::{Position as FilterPosition, Span};
+use wasm_filter::{format_filter, hover, try_parse};
+
+const SCHEMA_FILE: &str = "filter-schema.json";
+
+// The order of these is the index each semantic token's type is sent as
+const TOKEN_TYPES: [SemanticTokenType; 5] = [
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::KEYWORD
+];
+
+struct Server {
+    connection: Connection,
+    schema: Schema,
+    check_types: bool,          // Whether there's a schema to check against, rather than one that knows no fields
+    documents: HashMap<Uri, String>
+}
+
+fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+            legend: SemanticTokensLegend { token_types: TOKEN_TYPES.to_vec(), token_modifiers: Vec::new() },
+            full: Some(SemanticTokensFullOptions::Bool(true)),
+            ..SemanticTokensOptions::default()
+        })),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![" ".to_string(), "(".to_string(), "\"".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    };
+    let params: InitializeParams = serde_json::from_value(connection.initialize(serde_json::to_value(capabilities)?)?)?;
+
+    // An invalid schema shouldn't stop the server, so the user is told and only syntax is checked
+    let schema = match load_schema(&params) {
+        Ok(schema) => schema,
+        Err(error) => {
+            let message = format!("Only checking syntax, since the filter schema couldn't be loaded: {}", error);
+            let params = ShowMessageParams { typ: MessageType::ERROR, message };
+            connection.sender.send(Message::Notification(Notification::new(ShowMessage::METHOD.to_string(), params)))?;
+            None
+        }
+    };
+
+    // Running the server drops its connection when it's done, which lets the IO threads finish
+    Server { connection, check_types: schema.is_some(), schema: schema.unwrap_or_default(), documents: HashMap::new() }.run()?;
+    io_threads.join()?;
+    Ok(())
+}
+
+fn load_schema(params: &InitializeParams) -> Result<Option<Schema>, String> {
+    if let Some(schema) = params.initialization_options.as_ref().and_then(|options| options.get("schema")) {
+        return Schema::from_json(&schema.to_string()).map(Some);
+    }
+
+    #[allow(deprecated)]    // `root_uri` is deprecated in favour of workspace folders, but simpler clients only send it
+    let root = params.workspace_folders.as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| &folder.uri)
+        .or(params.root_uri.as_ref());
+    // The URI's path is percent-encoded, e.g. a space is `%20`
+    let directory = root.filter(|root| root.scheme().is_some_and(|scheme| scheme.as_str() == "file"))
+        .and_then(|root| root.path().as_estr().decode().into_string().ok())
+        .map(|path| PathBuf::from(path.as_ref()));
+    match directory.map(|directory| fs::read_to_string(directory.join(SCHEMA_FILE))) {
+        Some(Ok(json)) => Schema::from_json(&json).map(Some),
+        _ => Ok(None)
+    }
+}
+
+impl Server {
+    fn run(mut self) -> Result<(), Box<dyn Error + Sync + Send>> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    self.connection.sender.send(Message::Response(response))?;
+                },
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        match request.method.as_str() {
+            Formatting::METHOD => self.respond::<Formatting>(request, |params| self.formatting(&params.text_document.uri)),
+            Completion::METHOD => self.respond::<Completion>(request, |params| self.completion(&params.text_document_position)),
+            HoverRequest::METHOD => self.respond::<HoverRequest>(request, |params| self.hover(&params.text_document_position_params)),
+            SemanticTokensFullRequest::METHOD => self.respond::<SemanticTokensFullRequest>(request, |params| self.semantic_tokens(&params.text_document.uri)),
+            method => Response::new_err(request.id, ErrorCode::MethodNotFound as i32, format!("Unsupported method `{}`", method))
+        }
+    }
+
+    fn respond<R: lsp_types::request::Request>(&self, request: Request, handle: impl FnOnce(R::Params) -> R::Result) -> Response {
+        let id = request.id.clone();
+        match request.extract::<R::Params>(R::METHOD) {
+            Ok((_, params)) => Response::new_ok(id, handle(params)),
+            Err(error) => Response::new_err(id, ErrorCode::InvalidParams as i32, format!("Invalid params: {:?}", error))
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<(), Box<dyn Error + Sync + Send>> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => if let Some(params) = params::<DidOpenTextDocument>(notification) {
+                self.documents.insert(params.text_document.uri.clone(), params.text_document.text);
+                self.publish_diagnostics(params.text_document.uri)?;
+            },
+            DidChangeTextDocument::METHOD => if let Some(params) = params::<DidChangeTextDocument>(notification) {
+                // Syncing is `FULL`, so the last change is the whole new text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri.clone(), change.text);
+                    self.publish_diagnostics(params.text_document.uri)?;
+                }
+            },
+            DidCloseTextDocument::METHOD => if let Some(params) = params::<DidCloseTextDocument>(notification) {
+                self.documents.remove(&params.text_document.uri);
+                self.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(params.text_document.uri, Vec::new(), None))?;
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn notify<N: lsp_types::notification::Notification>(&self, params: N::Params) -> Result<(), Box<dyn Error + Sync + Send>> {
+        self.connection.sender.send(Message::Notification(Notification::new(N::METHOD.to_string(), params)))?;
+        Ok(())
+    }
+
+    fn publish_diagnostics(&self, uri: Uri) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let text = &self.documents[&uri];
+        let lines = LineIndex::new(text);
+        let errors = match try_parse(text) {
+            Ok(search) if self.check_types => schema::check(&search, &self.schema),
+            Ok(_) => Vec::new(),
+            Err(errors) => errors
+        };
+        let diagnostics = errors.into_iter()
+            .map(|error| Diagnostic {
+                range: lines.range(error.span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("filter".to_string()),
+                message: error.message,
+                ..Diagnostic::default()
+            })
+            .collect();
+
+        self.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(uri, diagnostics, None))
+    }
+
+    fn formatting(&self, uri: &Uri) -> Option<Vec<TextEdit>> {
+        let text = self.documents.get(uri)?;
+        // Filters that don't parse are left alone
+        let formatted = format_filter(text).ok()?;
+
+        let end = text.chars().fold(FilterPosition::default(), FilterPosition::after);
+        let range = LineIndex::new(text).range(Span::new(FilterPosition::default(), end));
+        Some(vec![TextEdit::new(range, formatted + "\n")])
+    }
+
+    fn completion(&self, position: &TextDocumentPositionParams) -> Option<CompletionResponse> {
+        let text = self.documents.get(&position.text_document.uri)?;
+        let lines = LineIndex::new(text);
+
+        let items = completion::complete(text, lines.offset(position.position), &self.schema).into_iter()
+            .enumerate()
+            .map(|(i, completion)| CompletionItem {
+                label: completion.replacement.clone(),
+                kind: Some(match completion.kind {
+                    CompletionKind::Field => CompletionItemKind::FIELD,
+                    CompletionKind::Comparator | CompletionKind::Paren => CompletionItemKind::OPERATOR,
+                    CompletionKind::Value => CompletionItemKind::VALUE,
+                    CompletionKind::JoinType => CompletionItemKind::KEYWORD
+                }),
+                detail: Some(completion.detail),
+                // Keep the server's ranking rather than the client sorting alphabetically
+                sort_text: Some(format!("{:04}", i)),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(lines.range(completion.span), completion.replacement))),
+                ..CompletionItem::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn hover(&self, position: &TextDocumentPositionParams) -> Option<Hover> {
+        let text = self.documents.get(&position.text_document.uri)?;
+        let lines = LineIndex::new(text);
+
+        let hover = hover(text, lines.offset(position.position), &self.schema)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value: hover.contents }),
+            range: Some(lines.range(hover.span))
+        })
+    }
+
+    fn semantic_tokens(&self, uri: &Uri) -> Option<SemanticTokensResult> {
+        let text = self.documents.get(uri)?;
+        let lines = LineIndex::new(text);
+
+        // Each token's position is sent relative to the one before it
+        let mut data = Vec::new();
+        let mut previous = lsp_types::Position::default();
+        for result in Lexer::new(text) {
+            let Ok(token) = result else {
+                continue;
+            };
+            let token_type = match token.to_bare().token {
+                BareToken::Name => 0,
+                BareToken::Comparator | BareToken::Paren => 1,
+                BareToken::String => 2,
+                BareToken::Number => 3,
+                BareToken::JoinType => 4,
+                BareToken::Error => continue
+            };
+
+            // Clients needn't support tokens spanning lines, so only a string's first line is highlighted
+            let start = lines.position(token.span.start);
+            let length = token.source.split('\n').next().unwrap_or_default().encode_utf16().count() as u32;
+            data.push(SemanticToken {
+                delta_line: start.line - previous.line,
+                delta_start: if start.line == previous.line { start.character - previous.character } else { start.character },
+                length,
+                token_type,
+                token_modifiers_bitset: 0
+            });
+            previous = start;
+        }
+
+        Some(SemanticTokensResult::Tokens(SemanticTokens { result_id: None, data }))
+    }
+}
+
+// The params of `notification`, or `None` if they're malformed. There's no response to report the error in, so it's
+// logged, and the notification is ignored rather than ending the server.
+fn params<N: lsp_types::notification::Notification>(notification: Notification) -> Option<N::Params> {
+    match notification.extract(N::METHOD) {
+        Ok(params) => Some(params),
+        Err(error) => {
+            eprintln!("Ignoring `{}` with invalid params: {:?}", N::METHOD, error);
+            None
+        }
+    }
+}
+
+// Converts between positions in the filter and LSP positions, which count lines and UTF-16 code units within a line
+struct LineIndex {
+    line_starts: Vec<usize>     // The UTF-16 offset of each line's start
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut offset = 0;
+        for c in text.chars() {
+            offset += c.len_utf16();
+            if c == '\n' {
+                line_starts.push(offset);
+            }
+        }
+        Self { line_starts }
+    }
+
+    fn position(&self, position: FilterPosition) -> lsp_types::Position {
+        lsp_types::Position::new(position.line as u32, (position.utf16 - self.line_starts[position.line]) as u32)
+    }
+
+    fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+
+    // The UTF-16 offset of `position` in the filter. Positions past the end of a line or the filter are clamped to it.
+    fn offset(&self, position: lsp_types::Position) -> usize {
+        let line = (position.line as usize).min(self.line_starts.len() - 1);
+        let line_end = self.line_starts.get(line + 1).map_or(usize::MAX, |next| next - 1);
+        (self.line_starts[line] + position.character as usize).min(line_end)
+    }
+}
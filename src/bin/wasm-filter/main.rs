@@ -0,0 +1,289 @@
+// Filters a stream of records from stdin, writing the ones that match to stdout, e.g.
+// `wasm-filter 'status = "open" & age > 30' < data.ndjson`.
+//
+// Exits with 0 if any records were written (or counted), 1 if none were, and 2 if the filter or the arguments are
+// invalid or the input can't be read, like `grep`.
+//
+// With `--repl FILE`, it instead loads FILE and prompts for filters to try against it (see `repl.rs`).
+
+mod repl;
+
+use std::env;
+use std::io::{self, stdin, stdout, BufRead, BufWriter, ErrorKind, IsTerminal, Write};
+use std::process::ExitCode;
+use csv::StringRecord;
+use serde_json::Value as Json;
+use wasm_filter::diagnostics::{self, Style};
+use wasm_filter::eval::{matches, optimize, Field, Record};
+use wasm_filter::parser::Search;
+use wasm_filter::try_parse;
+
+const USAGE: &str = "\
+Usage: wasm-filter [OPTIONS] FILTER
+       wasm-filter [--format FORMAT] --repl FILE
+
+Reads records from stdin and writes those matching FILTER to stdout, or with `--repl`, loads the records in FILE and
+prompts for filters to try against them.
+
+Options:
+  --format FORMAT  The format of the records: ndjson (one JSON object per line, the default), json (an array of
+                   objects), or csv (with a header row naming the fields). For `--repl`, it's guessed from
+                   FILE's extension by default.
+  -c, --count      Only print how many records matched
+  -v, --invert     Write the records that don't match instead
+  --repl FILE      Try filters interactively against the records in FILE
+  -h, --help       Print this message
+
+Exits with 0 if any records matched, 1 if none did, and 2 if there was an error.";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ndjson,
+    Json,
+    Csv
+}
+
+struct Options {
+    filter: String,
+    format: Option<Format>,
+    count: bool,
+    invert: bool,
+    repl: Option<String>
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if let Some(path) = &options.repl {
+        return match repl::run(path, options.format) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(message) => {
+                eprintln!("error: {}", message);
+                ExitCode::from(2)
+            }
+        };
+    }
+
+    let search = match try_parse(&options.filter) {
+        Ok(search) => optimize(&search),
+        Err(errors) => {
+            eprint!("{}", diagnostics::render_all(&options.filter, &errors, style(&io::stderr())));
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut output = BufWriter::new(stdout().lock());
+    let result = filter(&search, &options, stdin().lock(), &mut output).and_then(|written| {
+        output.flush().map_err(|error| error.to_string())?;
+        Ok(written)
+    });
+    match result {
+        Ok(0) => ExitCode::from(1),
+        Ok(_) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+// The options given by `args`, or `None` if help was asked for
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut filter = None;
+    let mut format = None;
+    let mut count = false;
+    let mut invert = false;
+    let mut repl = None;
+
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg, None)
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-c" | "--count" => count = true,
+            "-v" | "--invert" => invert = true,
+            "--format" => {
+                format = match value.or_else(|| args.next()).as_deref() {
+                    Some("ndjson") => Some(Format::Ndjson),
+                    Some("json") => Some(Format::Json),
+                    Some("csv") => Some(Format::Csv),
+                    Some(format) => return Err(format!("Unknown format `{}`; expected `ndjson`, `json` or `csv`", format)),
+                    None => return Err("Expected a format after `--format`".to_string())
+                };
+            },
+            "--repl" => match value.or_else(|| args.next()) {
+                Some(path) => repl = Some(path),
+                None => return Err("Expected a file after `--repl`".to_string())
+            },
+            flag if flag.starts_with('-') && flag.len() > 1 => return Err(format!("Unknown option `{}`", flag)),
+            _ if filter.is_some() => return Err(format!("Unexpected argument `{}`; quote the filter so it's one argument", flag)),
+            _ => filter = Some(flag)
+        }
+    }
+
+    if repl.is_some() {
+        return match filter {
+            Some(filter) => Err(format!("Unexpected argument `{}`; filters are typed at the prompt with `--repl`", filter)),
+            None => Ok(Some(Options { filter: String::new(), format, count, invert, repl }))
+        };
+    }
+    let filter = filter.ok_or("Expected a filter")?;
+    Ok(Some(Options { filter, format, count, invert, repl }))
+}
+
+// Writes the records from `input` that match (or with `--invert`, don't match) `search` to `output`, or just how many
+// there are with `--count`. Returns how many there were.
+fn filter(search: &Search, options: &Options, input: impl BufRead, output: &mut impl Write) -> Result<usize, String> {
+    let mut written = 0;
+    let mut keep = |record: &dyn Record| {
+        let keep = matches(search, record) != options.invert;
+        written += usize::from(keep);
+        keep
+    };
+
+    // When counting, the records are written nowhere
+    let mut sink = io::sink();
+    let records: &mut dyn Write = if options.count { &mut sink } else { output };
+    let result = match options.format.unwrap_or(Format::Ndjson) {
+        Format::Ndjson => filter_ndjson(input, records, &mut keep),
+        Format::Json => filter_json(input, records, &mut keep),
+        Format::Csv => filter_csv(input, records, &mut keep)
+    };
+    // Stop quietly if whatever's reading the output has stopped, e.g. `head`
+    match result {
+        Err(Error::Io(error)) if error.kind() == ErrorKind::BrokenPipe => return Ok(written),
+        result => result?
+    }
+
+    if options.count {
+        writeln!(output, "{}", written).map_err(|error| error.to_string())?;
+    }
+    Ok(written)
+}
+
+enum Error {
+    Io(io::Error),
+    Input(String)
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<Error> for String {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io(error) => error.to_string(),
+            Error::Input(message) => message
+        }
+    }
+}
+
+// Each line is written as it was read, so matching records keep their formatting
+fn filter_ndjson(input: impl BufRead, output: &mut dyn Write, keep: &mut dyn FnMut(&dyn Record) -> bool) -> Result<(), Error> {
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = match serde_json::from_str(&line) {
+            Ok(Json::Object(record)) => record,
+            Ok(_) => return Err(Error::Input(format!("Line {} isn't a JSON object", i + 1))),
+            Err(error) => return Err(Error::Input(format!("Line {} isn't valid JSON: {}", i + 1, error)))
+        };
+        if keep(&record) {
+            writeln!(output, "{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+fn filter_json(input: impl BufRead, output: &mut dyn Write, keep: &mut dyn FnMut(&dyn Record) -> bool) -> Result<(), Error> {
+    let records: Vec<Json> = serde_json::from_reader(input)
+        .map_err(|error| Error::Input(format!("Input isn't a JSON array: {}", error)))?;
+
+    let mut first = true;
+    for (i, record) in records.into_iter().enumerate() {
+        let Json::Object(record) = record else {
+            return Err(Error::Input(format!("Record {} isn't a JSON object", i + 1)));
+        };
+        if keep(&record) {
+            write!(output, "{}\n  {}", if first { "[" } else { "," }, Json::Object(record))?;
+            first = false;
+        }
+    }
+    writeln!(output, "{}", if first { "[]" } else { "\n]" })?;
+    Ok(())
+}
+
+fn filter_csv(input: impl BufRead, output: &mut dyn Write, keep: &mut dyn FnMut(&dyn Record) -> bool) -> Result<(), Error> {
+    let mut reader = csv::Reader::from_reader(input);
+    let mut writer = csv::Writer::from_writer(output);
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    writer.write_record(&headers).map_err(csv_error)?;
+
+    for row in reader.records() {
+        let row = row.map_err(csv_error)?;
+        if keep(&CsvRecord { headers: &headers, row: &row }) {
+            writer.write_record(&row).map_err(csv_error)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn csv_error(error: csv::Error) -> Error {
+    if !error.is_io_error() {
+        return Error::Input(format!("Invalid CSV: {}", error));
+    }
+    match error.into_kind() {
+        csv::ErrorKind::Io(error) => Error::Io(error),
+        _ => unreachable!()
+    }
+}
+
+// A CSV row, with fields named by the header row. Cells that read as a finite number are numbers, empty cells are
+// missing, and anything else is a string.
+struct CsvRecord<'r> {
+    headers: &'r StringRecord,
+    row: &'r StringRecord
+}
+
+impl<'r> CsvRecord<'r> {
+    // Like `get`, but borrowing from the row rather than from this
+    fn field(&self, name: &str) -> Option<Field<'r>> {
+        let cell = self.row.get(self.headers.iter().position(|header| header == name)?)?;
+        match cell.parse::<f64>() {
+            _ if cell.is_empty() => None,
+            Ok(number) if number.is_finite() => Some(Field::Number(number)),
+            _ => Some(Field::String(cell))
+        }
+    }
+}
+
+impl Record for CsvRecord<'_> {
+    fn get(&self, name: &str) -> Option<Field<'_>> {
+        self.field(name)
+    }
+}
+
+// Diagnostics are only colored for a person reading them, and not if they've asked for no color
+fn style(stream: &impl IsTerminal) -> Style {
+    if stream.is_terminal() && env::var_os("NO_COLOR").is_none() { Style::Ansi } else { Style::Plain }
+}
@@ -0,0 +1,420 @@
+// A prompt for trying filters against a file of records, e.g. `wasm-filter --repl data.csv`. Typing a filter prints how
+// many records match it and a sample of them, and commands starting with `:` show what's in the data and how a filter
+// is read.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+use csv::StringRecord;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde_json::{Map, Value as Json};
+use wasm_filter::diagnostics::{self, Style};
+use wasm_filter::eval::{matches, optimize, Field, Record};
+use wasm_filter::parser::{ComparisonOrSearch, Search};
+use wasm_filter::schema::describe_datetime;
+use wasm_filter::{lex_filter, try_parse};
+use wasm_filter::lexer::JoinType;
+use super::{style, CsvRecord, Format};
+
+// How many matching records are shown for a filter
+const SAMPLE_SIZE: usize = 5;
+
+const HELP: &str = "\
+Type a filter, e.g. `status = \"open\" & age > 30`, to see how many records match it and the first few that do.
+
+Commands:
+  :schema          List the fields in the records, with their types
+  :explain FILTER  Show how FILTER is grouped
+  :ast FILTER      Show the syntax tree FILTER is parsed to
+  :tokens FILTER   Show the tokens FILTER is split into
+  :help            Print this message
+  :quit            Exit (or press Ctrl-D)
+
+Up and down go through the filters typed before, which are kept between sessions.
+";
+
+pub fn run(path: &str, format: Option<Format>) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("Couldn't read `{}`: {}", path, error))?;
+    let dataset = Dataset::load(&text, format.unwrap_or_else(|| guess_format(path)))?;
+    let style = style(&stdout());
+    println!("Loaded {} from `{}`. Type a filter to try it, or `:help` for more.", plural(dataset.len(), "record"), path);
+
+    let mut editor = DefaultEditor::new().map_err(|error| error.to_string())?;
+    let history = history_path();
+    if let Some(history) = &history {
+        // There's no history the first time
+        let _ = editor.load_history(history);
+    }
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            // Ctrl-C abandons the line being typed, like in a shell
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error.to_string())
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line.as_str());
+
+        match evaluate(&dataset, &line, style) {
+            Some(output) => print!("{}", output),
+            None => break
+        }
+    }
+
+    if let Some(Err(error)) = history.map(|history| editor.save_history(&history)) {
+        eprintln!("warning: Couldn't save the history: {}", error);
+    }
+    Ok(())
+}
+
+// What to print for a line typed at the prompt, or `None` to exit
+fn evaluate(dataset: &Dataset, line: &str, style: Style) -> Option<String> {
+    let line = line.trim();
+    let Some(command) = line.strip_prefix(':') else {
+        return Some(search(dataset, line, style));
+    };
+    let (command, filter) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+    let filter = filter.trim();
+
+    let output = match command {
+        "q" | "quit" => return None,
+        "h" | "help" => HELP.to_string(),
+        "schema" => schema(dataset),
+        "explain" | "ast" | "tokens" if filter.is_empty() => format!("`:{}` needs a filter, e.g. `:{} age > 30`\n", command, command),
+        "explain" => parse_then(filter, style, |search| explain(search, 0)),
+        "ast" => parse_then(filter, style, |search| ast(search, 0)),
+        "tokens" => tokens(filter, style),
+        _ => format!("Unknown command `:{}`; type `:help` for the commands\n", command)
+    };
+    Some(output)
+}
+
+fn search(dataset: &Dataset, filter: &str, style: Style) -> String {
+    parse_then(filter, style, |search| {
+        let search = optimize(search);
+        let matching: Vec<usize> = (0..dataset.len()).filter(|&i| matches(&search, &dataset.record(i))).collect();
+
+        let mut output = format!("{} of {} match\n", matching.len(), plural(dataset.len(), "record"));
+        if let (Some(header), false) = (dataset.header(), matching.is_empty()) {
+            output += &format!("{}\n", header);
+        }
+        for &i in matching.iter().take(SAMPLE_SIZE) {
+            output += &format!("{}\n", dataset.show(i));
+        }
+        if matching.len() > SAMPLE_SIZE {
+            output += &format!("... and {} more\n", matching.len() - SAMPLE_SIZE);
+        }
+        output
+    })
+}
+
+// What `then` makes of `filter`, or its errors if it's invalid
+fn parse_then(filter: &str, style: Style, then: impl FnOnce(&Search) -> String) -> String {
+    match try_parse(filter) {
+        Ok(search) => then(&search),
+        Err(errors) => diagnostics::render_all(filter, &errors, style)
+    }
+}
+
+// Each field, with the types of its values as the evaluator sees them: strings that are all `"true"` or `"false"` are
+// bools, and strings that are all ISO 8601 dates or times are datetimes
+fn schema(dataset: &Dataset) -> String {
+    let mut output = String::new();
+    for name in dataset.fields() {
+        let (mut present, mut numbers) = (0, 0);
+        let (mut strings, mut bools, mut datetimes) = (0, 0, 0);
+        for i in 0..dataset.len() {
+            match dataset.record(i).get(name) {
+                Some(Field::Number(_)) => numbers += 1,
+                Some(Field::String(value)) => {
+                    strings += 1;
+                    bools += usize::from(value == "true" || value == "false");
+                    datetimes += usize::from(describe_datetime(value).is_some());
+                },
+                None => continue
+            }
+            present += 1;
+        }
+
+        let string_type = if bools == strings { "bool" } else if datetimes == strings { "datetime" } else { "string" };
+        let field_type = match (numbers, strings) {
+            (0, 0) => "never a number or string",
+            (_, 0) => "number",
+            (0, _) => string_type,
+            _ => "number or string"
+        };
+        output += &format!("{}: {}", name, field_type);
+        if present > 0 && present < dataset.len() {
+            output += &format!(" (in {} of {})", present, plural(dataset.len(), "record"));
+        }
+        output += "\n";
+    }
+    output
+}
+
+// e.g.
+//
+//     any of:
+//         status = "open"
+//         all of:
+//             age > 30
+//             name = "Ada"
+fn explain(search: &Search, depth: usize) -> String {
+    let label = match search.join_type {
+        JoinType::And => "all of",
+        JoinType::Or => "any of",
+        // An `Xor` chain holds when it has an odd number of operands that hold
+        JoinType::Xor => "an odd number of"
+    };
+
+    // A filter that's a single comparison is parsed to a search with one operand, which isn't worth a label
+    let (mut output, depth) = if search.comparisons.len() == 1 { (String::new(), depth) } else { (indented(depth, &format!("{}:", label)), depth + 1) };
+    for operand in &search.comparisons {
+        output += &match operand {
+            ComparisonOrSearch::Comparison(comparison) => indented(depth, &comparison.to_string()),
+            ComparisonOrSearch::Search(search) => explain(search, depth)
+        };
+    }
+    output
+}
+
+fn ast(search: &Search, depth: usize) -> String {
+    let mut output = indented(depth, &format!("Search {{ join_type: {:?} }}", search.join_type));
+    for operand in &search.comparisons {
+        output += &match operand {
+            ComparisonOrSearch::Comparison(comparison) => indented(depth + 1, &format!(
+                "Comparison {{ name: {:?}, comparator: {:?}, value: {:?} }}",
+                comparison.name, comparison.comparator, comparison.value
+            )),
+            ComparisonOrSearch::Search(search) => ast(search, depth + 1)
+        };
+    }
+    output
+}
+
+// Each token as its `BareTokenData`, with where it is and its source
+fn tokens(filter: &str, style: Style) -> String {
+    let data = lex_filter(filter);
+    let mut output = String::new();
+    for token in &data.tokens {
+        let start = token.span.start;
+        output += &format!(
+            "{:<10} {:<7} {}\n",
+            format!("{:?}", token.token), format!("{}:{}", start.line + 1, start.col + 1), token.span.slice(filter)
+        );
+    }
+    output + &diagnostics::render_all(filter, &data.errors, style)
+}
+
+fn indented(depth: usize, line: &str) -> String {
+    format!("{}{}\n", "    ".repeat(depth), line)
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+fn guess_format(path: &str) -> Format {
+    match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("csv") => Format::Csv,
+        Some(extension) if extension.eq_ignore_ascii_case("json") => Format::Json,
+        _ => Format::Ndjson
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".wasm_filter_history"))
+}
+
+// The records loaded from a file
+enum Dataset {
+    Json(Vec<Map<String, Json>>),
+    Csv { headers: StringRecord, rows: Vec<StringRecord> }
+}
+
+impl Dataset {
+    fn load(text: &str, format: Format) -> Result<Self, String> {
+        let object = |json, name: &str| match json {
+            Json::Object(record) => Ok(record),
+            _ => Err(format!("{} isn't a JSON object", name))
+        };
+
+        match format {
+            Format::Ndjson => text.lines().enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| {
+                    let json = serde_json::from_str(line).map_err(|error| format!("Line {} isn't valid JSON: {}", i + 1, error))?;
+                    object(json, &format!("Line {}", i + 1))
+                })
+                .collect::<Result<_, _>>()
+                .map(Dataset::Json),
+            Format::Json => serde_json::from_str::<Vec<Json>>(text)
+                .map_err(|error| format!("Input isn't a JSON array: {}", error))?
+                .into_iter().enumerate()
+                .map(|(i, json)| object(json, &format!("Record {}", i + 1)))
+                .collect::<Result<_, _>>()
+                .map(Dataset::Json),
+            Format::Csv => {
+                let mut reader = csv::Reader::from_reader(text.as_bytes());
+                let headers = reader.headers().map_err(|error| format!("Invalid CSV: {}", error))?.clone();
+                let rows = reader.records().collect::<Result<_, _>>().map_err(|error| format!("Invalid CSV: {}", error))?;
+                Ok(Dataset::Csv { headers, rows })
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Dataset::Json(records) => records.len(),
+            Dataset::Csv { rows, .. } => rows.len()
+        }
+    }
+
+    fn record(&self, i: usize) -> Row<'_> {
+        Row { dataset: self, i }
+    }
+
+    // Every field name, in the order of the CSV header row, or alphabetically for JSON
+    fn fields(&self) -> Vec<&str> {
+        match self {
+            Dataset::Json(records) => records.iter().flat_map(|record| record.keys().map(String::as_str)).collect::<BTreeSet<_>>().into_iter().collect(),
+            Dataset::Csv { headers, .. } => headers.iter().collect()
+        }
+    }
+
+    // The line to print above records, if they need one
+    fn header(&self) -> Option<String> {
+        match self {
+            Dataset::Json(_) => None,
+            Dataset::Csv { headers, .. } => Some(csv_line(headers))
+        }
+    }
+
+    // The `i`th record, as it would be written by `wasm-filter`
+    fn show(&self, i: usize) -> String {
+        match self {
+            Dataset::Json(records) => Json::Object(records[i].clone()).to_string(),
+            Dataset::Csv { rows, .. } => csv_line(&rows[i])
+        }
+    }
+}
+
+fn csv_line(row: &StringRecord) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing to memory can't fail
+    writer.write_record(row).unwrap();
+    let line = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    line.trim_end().to_string()
+}
+
+struct Row<'d> {
+    dataset: &'d Dataset,
+    i: usize
+}
+
+impl Record for Row<'_> {
+    fn get(&self, name: &str) -> Option<Field<'_>> {
+        match self.dataset {
+            Dataset::Json(records) => Record::get(&records[self.i], name),
+            Dataset::Csv { headers, rows } => CsvRecord { headers, row: &rows[self.i] }.field(name)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod repl_tests {
+    use super::*;
+
+    fn dataset() -> Dataset {
+        Dataset::load(r#"
+            {"name": "Ada", "status": "open", "age": 36, "active": "true", "joined": "2024-01-31"}
+            {"name": "Grace", "status": "closed", "age": 85, "active": "false", "joined": "2023-12-09T10:00:00Z"}
+            {"name": "Alan", "status": "open", "age": 41, "active": "true", "joined": "2022-06-23", "tags": []}
+            {"name": "Linus", "status": "open", "age": "unknown"}
+        "#, Format::Ndjson).unwrap()
+    }
+
+    fn run(line: &str) -> String {
+        evaluate(&dataset(), line, Style::Plain).unwrap()
+    }
+
+    #[test]
+    pub fn counts_and_samples_matches() {
+        assert_eq!(run(r#"status = "open" & age > 40"#), "1 of 4 records match\n{\"active\":\"true\",\"age\":41,\"joined\":\"2022-06-23\",\"name\":\"Alan\",\"status\":\"open\",\"tags\":[]}\n");
+        assert_eq!(run("age > 100"), "0 of 4 records match\n");
+
+        let rows: String = (0..8).map(|i| format!("{},{}\n", i, i % 2)).collect();
+        let csv = Dataset::load(&format!("id,odd\n{}", rows), Format::Csv).unwrap();
+        assert_eq!(evaluate(&csv, "odd = 0 | odd = 1", Style::Plain).unwrap(), "8 of 8 records match\nid,odd\n0,0\n1,1\n2,0\n3,1\n4,0\n... and 3 more\n");
+    }
+
+    #[test]
+    pub fn shows_errors_in_filters() {
+        assert_eq!(run("age <> 1"), "error: `<>` isn't a comparator; did you mean `!=`?\n --> 1:5\n  |\n1 | age <> 1\n  |     ^^\n  = help: Replace with `!=`\n");
+    }
+
+    #[test]
+    pub fn lists_the_schema() {
+        assert_eq!(run(":schema"), "\
+active: bool (in 3 of 4 records)
+age: number or string
+joined: datetime (in 3 of 4 records)
+name: string
+status: string
+tags: never a number or string
+");
+    }
+
+    #[test]
+    pub fn explains_grouping() {
+        assert_eq!(run(r#":explain status = "open" | age > 30 & name = "Ada""#), "\
+any of:
+    status = \"open\"
+    all of:
+        age > 30
+        name = \"Ada\"
+");
+        assert_eq!(run(":explain age > 30"), "age > 30\n");
+    }
+
+    #[test]
+    pub fn shows_the_ast() {
+        assert_eq!(run(r#":ast (age > 30 ^ age < 40) & name = "Ada""#), "\
+Search { join_type: And }
+    Search { join_type: Xor }
+        Comparison { name: \"age\", comparator: GreaterThan, value: Number(30.0) }
+        Comparison { name: \"age\", comparator: LessThan, value: Number(40.0) }
+    Comparison { name: \"name\", comparator: Equal, value: String(\"Ada\") }
+");
+    }
+
+    #[test]
+    pub fn shows_tokens() {
+        assert_eq!(run(":tokens age >= 1,000\n& x = \"y\""), "\
+Name       1:1     age
+Comparator 1:5     >=
+Number     1:8     1,000
+JoinType   2:1     &
+Name       2:3     x
+Comparator 2:5     =
+String     2:7     \"y\"
+");
+        assert!(run(":tokens a = #").starts_with("Name       1:1     a\nComparator 1:3     =\nError      1:5     #\nerror: "));
+    }
+
+    #[test]
+    pub fn handles_commands() {
+        assert!(run(":help").starts_with("Type a filter"));
+        assert_eq!(run(":explain"), "`:explain` needs a filter, e.g. `:explain age > 30`\n");
+        assert_eq!(run(":nope"), "Unknown command `:nope`; type `:help` for the commands\n");
+        assert_eq!(evaluate(&dataset(), ":quit", Style::Plain), None);
+    }
+}
@@ -0,0 +1,282 @@
+use wasm_bindgen::prelude::*;
+use crate::lexer::{Comparator, JoinType, Lexer, Token, Value};
+use crate::schema::{FieldType, Schema};
+use crate::types::{Position, Span};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompletionKind {
+    Field,
+    Comparator,
+    Value,
+    JoinType,
+    Paren
+}
+
+// Something that could come next in a filter. Applying it replaces the text in `span` (the partly typed token at the
+// cursor, or nothing) with `replacement`.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Completion {
+    pub kind: CompletionKind,
+    pub replacement: String,
+    pub detail: String,         // e.g. a field's type, or what a comparator means
+    pub span: Span
+}
+
+// What the filter before the cursor is waiting for
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Expecting<'t> {
+    Operand,                    // At the start, or after a join or `(`
+    Comparator(&'t str),        // After a field name
+    Value(&'t str),             // After a comparator, with the field it compares
+    Join,                       // After a comparison or `)`
+    Nothing                     // After a syntax error
+}
+
+// Suggests what could be typed at `cursor`, counted in UTF-16 code units as in a JavaScript string, best first.
+//
+// If the cursor is in or at the end of a token, that token is being typed: suggestions replace it and have to match
+// what's been typed of it so far. Fields come from `schema`, and a field's type limits the comparators and values
+// suggested after it.
+pub fn complete(text: &str, cursor: usize, schema: &Schema) -> Vec<Completion> {
+    // Errors are kept as tokens without a `Token`, so that e.g. a lone `!` can be completed to `!=`
+    let tokens: Vec<_> = Lexer::new(text)
+        .map(|result| match result {
+            Ok(token) => (Some(token.token), token.span),
+            Err(error) => (None, error.range)
+        })
+        .take_while(|(_, span)| span.start.utf16 < cursor)
+        .collect();
+
+    // The token being typed, if the cursor is inside it or at the end of one that could go on
+    let (typed, tokens) = match tokens.split_last() {
+        Some((last, rest)) if cursor < last.1.end.utf16 || (cursor == last.1.end.utf16 && can_continue(last, text)) => (Some(last), rest),
+        _ => (None, &tokens[..])
+    };
+    let (prefix, span) = match typed {
+        Some((_, span)) => (prefix_of(span.slice(text), cursor - span.start.utf16), *span),
+        None => ("", Span::new(position_at(text, cursor), position_at(text, cursor)))
+    };
+
+    let mut depth = 0usize;
+    let mut expecting = Expecting::Operand;
+    for (token, _) in tokens {
+        expecting = match (token, expecting) {
+            (Some(Token::OpenParen), Expecting::Operand) => { depth += 1; Expecting::Operand },
+            (Some(Token::Name(name)), Expecting::Operand) => Expecting::Comparator(name),
+            (Some(Token::Comparator(_)), Expecting::Comparator(name)) => Expecting::Value(name),
+            (Some(Token::Value(_)), Expecting::Value(_)) => Expecting::Join,
+            (Some(Token::CloseParen), Expecting::Join) if depth > 0 => { depth -= 1; Expecting::Join },
+            (Some(Token::JoinType(_)), Expecting::Join) => Expecting::Operand,
+            _ => Expecting::Nothing
+        };
+    }
+
+    let mut completions = Vec::new();
+    let mut add = |kind, replacement: String, detail: String| {
+        completions.push(Completion { kind, replacement, detail, span });
+    };
+    match expecting {
+        Expecting::Operand => {
+            let mut fields: Vec<_> = schema.fields().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            for (name, field_type) in fields {
+                add(CompletionKind::Field, name.to_string(), type_name(field_type).to_string());
+            }
+            add(CompletionKind::Paren, "(".to_string(), "Start a group".to_string());
+        },
+        Expecting::Comparator(name) => {
+            let ordered = schema.field(name).is_none_or(FieldType::is_ordered);
+            for comparator in COMPARATORS {
+                if ordered || matches!(comparator, Comparator::Equal | Comparator::NotEqual) {
+                    add(CompletionKind::Comparator, comparator.to_string(), comparator_meaning(comparator).to_string());
+                }
+            }
+        },
+        Expecting::Value(name) => {
+            let values = match schema.field(name) {
+                Some(FieldType::Bool) => vec!["true".to_string(), "false".to_string()],
+                Some(FieldType::Enum(values)) => values.clone(),
+                _ => Vec::new()
+            };
+            for value in values {
+                add(CompletionKind::Value, format!("\"{}\"", value), name.to_string());
+            }
+        },
+        Expecting::Join => {
+            for join_type in [JoinType::And, JoinType::Or, JoinType::Xor] {
+                add(CompletionKind::JoinType, join_type.to_string(), join_meaning(join_type).to_string());
+            }
+            if depth > 0 {
+                add(CompletionKind::Paren, ")".to_string(), "End the group".to_string());
+            }
+        },
+        Expecting::Nothing => {}
+    }
+
+    rank(completions, prefix)
+}
+
+const COMPARATORS: [Comparator; 6] = [
+    Comparator::Equal,
+    Comparator::NotEqual,
+    Comparator::LessThan,
+    Comparator::GreaterThan,
+    Comparator::LessThanOrEqual,
+    Comparator::GreaterThanOrEqual
+];
+
+// Whether typing more at the end of `token` could still change it, e.g. a name or `<` (which could become `<=`), but
+// not `&` or a closed string
+fn can_continue((token, span): &(Option<Token>, Span), text: &str) -> bool {
+    let source = span.slice(text);
+    match token {
+        Some(Token::Name(_) | Token::Value(Value::Number(_))) | None => true,
+        Some(Token::Comparator(_)) => source == "<" || source == ">",
+        Some(Token::Value(Value::String(_))) => source.len() < 2 || !source.ends_with('"'),
+        Some(Token::JoinType(_) | Token::OpenParen | Token::CloseParen) => false
+    }
+}
+
+// Keeps the completions that start with `prefix`, ignoring case, followed by those that merely contain it. Otherwise
+// completions keep their order.
+fn rank(completions: Vec<Completion>, prefix: &str) -> Vec<Completion> {
+    let prefix = prefix.to_lowercase();
+    let (starts_with, rest): (Vec<_>, Vec<_>) = completions.into_iter()
+        .filter(|completion| completion.replacement.to_lowercase().contains(&prefix))
+        .partition(|completion| completion.replacement.to_lowercase().starts_with(&prefix));
+
+    starts_with.into_iter().chain(rest).collect()
+}
+
+// The first `utf16` code units of `source`
+fn prefix_of(source: &str, utf16: usize) -> &str {
+    let mut length = 0;
+    for (i, c) in source.char_indices() {
+        if length >= utf16 {
+            return &source[..i];
+        }
+        length += c.len_utf16();
+    }
+    source
+}
+
+// The position `utf16` code units into `text`, or its end if it's shorter
+fn position_at(text: &str, utf16: usize) -> Position {
+    let mut position = Position::default();
+    for c in text.chars() {
+        if position.utf16 >= utf16 {
+            break;
+        }
+        position = position.after(c);
+    }
+    position
+}
+
+pub fn type_name(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Number => "number",
+        FieldType::String => "string",
+        FieldType::Bool => "bool",
+        FieldType::Datetime => "datetime",
+        FieldType::Enum(_) => "enum"
+    }
+}
+
+pub fn comparator_meaning(comparator: Comparator) -> &'static str {
+    match comparator {
+        Comparator::Equal => "Equal to",
+        Comparator::NotEqual => "Not equal to",
+        Comparator::LessThan => "Less than",
+        Comparator::GreaterThan => "Greater than",
+        Comparator::LessThanOrEqual => "Less than or equal to",
+        Comparator::GreaterThanOrEqual => "Greater than or equal to"
+    }
+}
+
+pub fn join_meaning(join_type: JoinType) -> &'static str {
+    match join_type {
+        JoinType::And => "Both sides match",
+        JoinType::Or => "Either side matches",
+        JoinType::Xor => "Exactly one side matches"
+    }
+}
+
+
+
+#[cfg(test)]
+mod completion_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_number("age");
+        schema.add_string("name");
+        schema.add_bool("active");
+        schema.add_enum("status", vec!["open".to_string(), "closed".to_string()]);
+        schema
+    }
+
+    // Completes with the cursor at the end of `text`
+    fn replacements(text: &str) -> Vec<String> {
+        complete(text, text.encode_utf16().count(), &schema()).into_iter().map(|completion| completion.replacement).collect()
+    }
+
+    #[test]
+    fn suggests_fields_where_a_comparison_can_start() {
+        for text in ["", "age = 1 & ", "age = 1 &", "(("] {
+            assert_eq!(replacements(text), ["active", "age", "name", "status", "("], "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn filters_by_and_replaces_the_typed_token() {
+        let text = "age = 1 | ag";
+        let completions = complete(text, 12, &schema());
+
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].replacement, "age");
+        assert_eq!(completions[0].span.slice(text), "ag");
+
+        // Starting with what's typed ranks above merely containing it
+        assert_eq!(replacements("a"), ["active", "age", "name", "status"]);
+        // Only what's before the cursor counts
+        let completions = complete("stx = 1", 2, &schema());
+        assert_eq!(completions[0].replacement, "status");
+        assert_eq!(completions[0].span.slice("stx = 1"), "stx");
+    }
+
+    #[test]
+    fn suggests_comparators_for_the_field_type() {
+        assert_eq!(replacements("age "), ["=", "!=", "<", ">", "<=", ">="]);
+        assert_eq!(replacements("status "), ["=", "!="]);
+        assert_eq!(replacements("age <"), ["<", "<="]);
+        assert_eq!(replacements("age !"), ["!="]);
+        // Unknown fields could be anything
+        assert_eq!(replacements("unknown ").len(), 6);
+    }
+
+    #[test]
+    fn suggests_values_for_bools_and_enums() {
+        assert_eq!(replacements("active = "), ["\"true\"", "\"false\""]);
+        assert_eq!(replacements("status != \"c"), ["\"closed\""]);
+        assert_eq!(replacements("age = "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn suggests_joins_and_closing_parens() {
+        assert_eq!(replacements("age = 1 "), ["&", "|", "^"]);
+        assert_eq!(replacements("status = \"open\""), ["&", "|", "^"]);
+        assert_eq!(replacements("(age = 1 "), ["&", "|", "^", ")"]);
+        assert_eq!(replacements("(age = 1) "), ["&", "|", "^"]);
+        assert_eq!(replacements("((a = 1) "), ["&", "|", "^", ")"]);
+    }
+
+    #[test]
+    fn suggests_nothing_after_a_syntax_error() {
+        assert_eq!(replacements("age age "), Vec::<String>::new());
+        assert_eq!(replacements("a = 1) "), Vec::<String>::new());
+        assert_eq!(replacements("a @ 1 & "), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,286 @@
+// Renders `FilterError`s for a terminal, in the style of rustc, e.g.
+//
+//     error: Unknown field `agee`; did you mean `age`?
+//      --> 1:14
+//       |
+//     1 | status = 1 & agee > 30
+//       |              ^^^^-----
+//       = help: Replace with `age`
+//
+// The error's span is underlined with `^`, and the rest of its range with `-`. Tabs are expanded to `TAB_WIDTH` spaces,
+// like rustc does, so the underline lines up however the terminal would have shown them.
+
+use crate::types::{FilterError, Span};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Style {
+    Plain,
+    // Colored with ANSI escape codes, with the context range dimmed
+    Ansi
+}
+
+const TAB_WIDTH: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+// What a character of the filter is part of
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Mark {
+    None,
+    Range,
+    Span
+}
+
+// Renders `error`, which came from `filter`, ending with a newline
+pub fn render(filter: &str, error: &FilterError, style: Style) -> String {
+    let paint = |color: &str, text: &str| match style {
+        Style::Plain => text.to_string(),
+        Style::Ansi if text.is_empty() => String::new(),
+        Style::Ansi => format!("{}{}{}", color, text, RESET)
+    };
+
+    let lines: Vec<&str> = filter.split('\n').collect();
+    let span = error.span;
+    debug_assert!(contains(error.range, span), "the range of {:?} doesn't contain its span", error);
+    // Errors can be built or deserialized with any range, and one that doesn't contain the span is left out rather than
+    // underlining the wrong text
+    let range = if contains(error.range, span) { error.range } else { span };
+    let (first, last) = (range.start.line, last_line(range).min(lines.len() - 1));
+
+    let number_width = (last + 1).to_string().len();
+    let gutter = |number: &str| paint(BLUE, &format!("{:>width$} |", number, width = number_width));
+
+    let mut output = format!("{}{}\n", paint(RED, "error"), paint(BOLD, &format!(": {}", error.message)));
+    output += &format!("{}{} {}:{}\n", " ".repeat(number_width), paint(BLUE, "-->"), span.start.line + 1, span.start.col + 1);
+    output += &format!("{}\n", gutter(""));
+
+    for (line, source) in lines.iter().enumerate().take(last + 1).skip(first) {
+        // Lines from Windows files keep their `\r`, which would send the cursor back to the start of the line
+        let chars: Vec<char> = source.trim_end_matches('\r').chars().collect();
+        let mut marks: Vec<Mark> = (0..chars.len()).map(|col| mark(line, col, span, range)).collect();
+        // An empty span, e.g. at the end of the filter, still needs something to point at it
+        if span.is_empty() && span.start.line == line {
+            marks.resize(marks.len().max(span.start.col + 1), Mark::None);
+            marks[span.start.col] = Mark::Span;
+        }
+        let (chars, marks) = expand_tabs(&chars, &marks);
+
+        let source = paint_source(&chars, &marks, style);
+        output += &format!("{}{}{}\n", gutter(&(line + 1).to_string()), if source.is_empty() { "" } else { " " }, source);
+
+        let Some(end) = marks.iter().rposition(|mark| *mark != Mark::None) else {
+            continue;
+        };
+        let mut underline = String::new();
+        for (mark, run) in runs(&marks[..=end]) {
+            underline += &match mark {
+                Mark::None => " ".repeat(run),
+                Mark::Range => paint(DIM, &"-".repeat(run)),
+                Mark::Span => paint(RED, &"^".repeat(run))
+            };
+        }
+        output += &format!("{} {}\n", gutter(""), underline);
+    }
+
+    for fix in &error.fixes {
+        output += &format!("{} {} {}\n", " ".repeat(number_width), paint(BLUE, "="), paint(CYAN, "help") + ": " + &fix.title);
+    }
+    output
+}
+
+// Renders each of `errors` from `filter`, separated by blank lines
+pub fn render_all(filter: &str, errors: &[FilterError], style: Style) -> String {
+    errors.iter().map(|error| render(filter, error, style)).collect::<Vec<_>>().join("\n")
+}
+
+// `chars` and their `marks` with each tab replaced by `TAB_WIDTH` spaces, marked like the tab was
+fn expand_tabs(chars: &[char], marks: &[Mark]) -> (Vec<char>, Vec<Mark>) {
+    let mut expanded_chars = Vec::with_capacity(chars.len());
+    let mut expanded_marks = Vec::with_capacity(marks.len());
+    // There can be a mark past the end of the line, for an empty span there
+    for (i, &mark) in marks.iter().enumerate() {
+        let width = if chars.get(i) == Some(&'\t') { TAB_WIDTH } else { 1 };
+        expanded_marks.extend(std::iter::repeat_n(mark, width));
+    }
+    for &c in chars {
+        match c {
+            '\t' => expanded_chars.extend(std::iter::repeat_n(' ', TAB_WIDTH)),
+            c => expanded_chars.push(c)
+        }
+    }
+    (expanded_chars, expanded_marks)
+}
+
+// The source of a line, with the parts of the context range outside the span dimmed
+fn paint_source(chars: &[char], marks: &[Mark], style: Style) -> String {
+    if style == Style::Plain {
+        return chars.iter().collect();
+    }
+
+    let mut painted = String::new();
+    let mut col = 0;
+    for (mark, run) in runs(&marks[..chars.len().min(marks.len())]) {
+        let text: String = chars[col..col + run].iter().collect();
+        painted += &if mark == Mark::Range { format!("{}{}{}", DIM, text, RESET) } else { text };
+        col += run;
+    }
+    painted + &chars[col..].iter().collect::<String>()
+}
+
+// Each run of the same mark, with how long it is
+fn runs(marks: &[Mark]) -> Vec<(Mark, usize)> {
+    let mut runs: Vec<(Mark, usize)> = Vec::new();
+    for &mark in marks {
+        match runs.last_mut() {
+            Some((last, run)) if *last == mark => *run += 1,
+            _ => runs.push((mark, 1))
+        }
+    }
+    runs
+}
+
+fn mark(line: usize, col: usize, span: Span, range: Span) -> Mark {
+    if covers(span, line, col) {
+        Mark::Span
+    }
+    else if covers(range, line, col) {
+        Mark::Range
+    }
+    else {
+        Mark::None
+    }
+}
+
+// Whether `span` covers the character at `col` of `line`
+fn covers(span: Span, line: usize, col: usize) -> bool {
+    (line, col) >= (span.start.line, span.start.col) && (line, col) < (span.end.line, span.end.col)
+}
+
+fn contains(outer: Span, inner: Span) -> bool {
+    outer.start.byte <= inner.start.byte && inner.end.byte <= outer.end.byte
+}
+
+// The last line with any of `span` on it. A span that ends just after a newline doesn't reach the next line.
+fn last_line(span: Span) -> usize {
+    if span.end.col == 0 && span.end.line > span.start.line { span.end.line - 1 } else { span.end.line }
+}
+
+
+#[cfg(test)]
+mod diagnostics_tests {
+    use super::*;
+    use crate::schema::{check, Schema};
+    use crate::types::Position;
+
+    fn first_error(filter: &str) -> FilterError {
+        crate::try_parse(filter).err().unwrap().remove(0)
+    }
+
+    #[test]
+    pub fn underlines_the_span() {
+        assert_eq!(render("age <> 30", &first_error("age <> 30"), Style::Plain), "\
+error: `<>` isn't a comparator; did you mean `!=`?
+ --> 1:5
+  |
+1 | age <> 30
+  |     ^^
+  = help: Replace with `!=`
+");
+    }
+
+    #[test]
+    pub fn underlines_the_context_range() {
+        let mut schema = Schema::new();
+        schema.add_number("age");
+        let filter = "status = 1 |\n  agee > 30";
+        let errors = check(&crate::try_parse(filter).unwrap(), &schema);
+
+        assert_eq!(render(filter, &errors[1], Style::Plain), "\
+error: Unknown field `agee`; did you mean `age`?
+ --> 2:3
+  |
+2 |   agee > 30
+  |   ^^^^-----
+  = help: Replace with `age`
+");
+    }
+
+    #[test]
+    pub fn underlines_every_line_of_multi_line_spans() {
+        let mut schema = Schema::new();
+        schema.add_number("age");
+        let filter = "age = \"not\n\na number\" & age = 1";
+        let errors = check(&crate::try_parse(filter).unwrap(), &schema);
+
+        assert_eq!(render(filter, &errors[0], Style::Plain), "\
+error: Field `age` is a number, but `\"not\n\na number\"` is a string
+ --> 1:7
+  |
+1 | age = \"not
+  | ------^^^^
+2 |
+3 | a number\" & age = 1
+  | ^^^^^^^^^
+");
+    }
+
+    #[test]
+    pub fn expands_tabs() {
+        let mut schema = Schema::new();
+        schema.add_number("age");
+        let filter = "status = 1 |\n\tagee\t> 30";
+        let errors = check(&crate::try_parse(filter).unwrap(), &schema);
+
+        assert_eq!(render(filter, &errors[1], Style::Plain), "\
+error: Unknown field `agee`; did you mean `age`?
+ --> 2:2
+  |
+2 |     agee    > 30
+  |     ^^^^--------
+  = help: Replace with `age`
+");
+
+        let rendered = render("age\t<>\t1", &first_error("age\t<>\t1"), Style::Plain);
+        assert_eq!(rendered.lines().collect::<Vec<_>>()[3..5], ["1 | age    <>    1", "  |        ^^"]);
+    }
+
+    #[test]
+    pub fn numbers_lines_to_the_same_width() {
+        let filter = format!("{}a = 1 a = 2", "a = 1 &\n".repeat(9));
+        let rendered = render(&filter, &first_error(&filter), Style::Plain);
+
+        assert!(rendered.starts_with("error: Expected `&`, `|` or `^` between comparisons\n  --> 1:1\n   |\n 1 | a = 1 &\n   | ^^^^^^^\n"));
+        assert!(rendered.ends_with("10 | a = 1 a = 2\n   | ^^^^^^^^^^^\n"));
+    }
+
+    #[test]
+    pub fn points_at_empty_spans() {
+        let end = Position { byte: 4, char: 4, utf16: 4, line: 0, col: 4 };
+        let error = FilterError::new("Expected a value".to_string(), Span::new(end, end));
+
+        assert_eq!(render("a =-", &error, Style::Plain), "error: Expected a value\n --> 1:5\n  |\n1 | a =-\n  |     ^\n");
+    }
+
+    #[test]
+    pub fn colors_with_ansi() {
+        let mut schema = Schema::new();
+        schema.add_number("age");
+        let filter = "agee > 30";
+        let errors = check(&crate::try_parse(filter).unwrap(), &schema);
+
+        assert_eq!(render(filter, &errors[0], Style::Ansi), [
+            "\x1b[1;31merror\x1b[0m\x1b[1m: Unknown field `agee`; did you mean `age`?\x1b[0m",
+            " \x1b[1;34m-->\x1b[0m 1:1",
+            "\x1b[1;34m  |\x1b[0m",
+            "\x1b[1;34m1 |\x1b[0m agee\x1b[2m > 30\x1b[0m",
+            "\x1b[1;34m  |\x1b[0m \x1b[1;31m^^^^\x1b[0m\x1b[2m-----\x1b[0m",
+            "  \x1b[1;34m=\x1b[0m \x1b[1;36mhelp\x1b[0m: Replace with `age`",
+            ""
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use serde_json::{json, Value as Json};
+use crate::lexer::{Comparator, JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+use crate::translate::check_xor_expansion;
+
+// How `=` and `!=` are matched for a field: `Term` looks for the exact value (keyword and numeric fields), while `Match`
+// runs the value through the field's analyzer (full-text fields)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EqualityQuery {
+    #[default]
+    Term,
+    Match
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct QueryOptions {
+    pub default_equality: EqualityQuery,
+    pub fields: HashMap<String, EqualityQuery>
+}
+
+impl QueryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_equality(&mut self, field: &str, equality: EqualityQuery) {
+        self.fields.insert(field.to_string(), equality);
+    }
+
+    fn equality(&self, field: &str) -> EqualityQuery {
+        self.fields.get(field).copied().unwrap_or(self.default_equality)
+    }
+}
+
+// Translates `search` into an Elasticsearch/OpenSearch query clause, to be sent as `{"query": ...}`.
+//
+// `And` becomes a `bool` query's `must` (with `!=` comparisons moved into its `must_not`), `Or` becomes `should`, and
+// nested joins become nested `bool` queries. There's no boolean xor, so `a ^ b` is rewritten as
+// `(a & !b) | (!a & b)`, folding longer chains from the left, and ones too long to write out this way are an error (see
+// `translate`). Note that `must_not` also matches documents without the field, as it does natively.
+pub fn to_elasticsearch(search: &Search, options: &QueryOptions) -> Result<Json, String> {
+    check_xor_expansion(search)?;
+    Ok(search_to_elasticsearch(search, options))
+}
+
+fn search_to_elasticsearch(search: &Search, options: &QueryOptions) -> Json {
+    match search.join_type {
+        _ if search.comparisons.len() == 1 => operand_to_elasticsearch(&search.comparisons[0], options),
+
+        JoinType::And if search.comparisons.is_empty() => json!({ "match_all": {} }),
+        JoinType::And => {
+            let mut must = Vec::new();
+            let mut must_not = Vec::new();
+            for operand in search.comparisons.iter() {
+                match operand {
+                    ComparisonOrSearch::Comparison(comparison) if comparison.comparator == Comparator::NotEqual =>
+                        must_not.push(equality_query(comparison, options)),
+                    operand => must.push(operand_to_elasticsearch(operand, options))
+                }
+            }
+
+            if must_not.is_empty() {
+                json!({ "bool": { "must": must } })
+            }
+            else if must.is_empty() {
+                json!({ "bool": { "must_not": must_not } })
+            }
+            else {
+                json!({ "bool": { "must": must, "must_not": must_not } })
+            }
+        },
+
+        // An empty `Or` or `Xor` has no operand that could hold, so match nothing
+        JoinType::Or | JoinType::Xor if search.comparisons.is_empty() => json!({ "match_none": {} }),
+        JoinType::Or => json!({ "bool": {
+            "should": search.comparisons.iter().map(|operand| operand_to_elasticsearch(operand, options)).collect::<Vec<_>>(),
+            "minimum_should_match": 1
+        }}),
+
+        JoinType::Xor => search.comparisons.iter()
+            .map(|operand| operand_to_elasticsearch(operand, options))
+            .reduce(|left, right| json!({ "bool": {
+                "should": [
+                    { "bool": { "must": [left.clone()], "must_not": [right.clone()] } },
+                    { "bool": { "must": [right], "must_not": [left] } }
+                ],
+                "minimum_should_match": 1
+            }}))
+            .unwrap()
+    }
+}
+
+fn operand_to_elasticsearch(operand: &ComparisonOrSearch, options: &QueryOptions) -> Json {
+    match operand {
+        ComparisonOrSearch::Comparison(comparison) => comparison_to_elasticsearch(comparison, options),
+        ComparisonOrSearch::Search(search) => search_to_elasticsearch(search, options)
+    }
+}
+
+fn comparison_to_elasticsearch(comparison: &Comparison, options: &QueryOptions) -> Json {
+    let range = match comparison.comparator {
+        Comparator::Equal => return equality_query(comparison, options),
+        Comparator::NotEqual => return json!({ "bool": { "must_not": [equality_query(comparison, options)] } }),
+        Comparator::LessThan => "lt",
+        Comparator::GreaterThan => "gt",
+        Comparator::LessThanOrEqual => "lte",
+        Comparator::GreaterThanOrEqual => "gte"
+    };
+
+    json!({ "range": { comparison.name.as_ref(): { range: value_to_json(&comparison.value) } } })
+}
+
+fn equality_query(comparison: &Comparison, options: &QueryOptions) -> Json {
+    let query = match options.equality(&comparison.name) {
+        EqualityQuery::Term => "term",
+        EqualityQuery::Match => "match"
+    };
+
+    json!({ query: { comparison.name.as_ref(): value_to_json(&comparison.value) } })
+}
+
+fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Number(number) => json!(number),
+        Value::String(string) => json!(string)
+    }
+}
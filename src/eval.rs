@@ -0,0 +1,299 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde_json::{Map, Value as Json};
+use crate::lexer::{Comparator, JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+
+// A field's value as seen by the evaluator. Anything that isn't a number or string (e.g. `null` or an array) is treated
+// the same as a missing field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field<'a> {
+    Number(f64),
+    String(&'a str)
+}
+
+pub trait Record {
+    fn get(&self, name: &str) -> Option<Field<'_>>;
+}
+
+impl Record for HashMap<String, Value<'_>> {
+    fn get(&self, name: &str) -> Option<Field<'_>> {
+        match HashMap::get(self, name)? {
+            Value::Number(number) => Some(Field::Number(*number)),
+            Value::String(string) => Some(Field::String(string))
+        }
+    }
+}
+
+impl Record for Map<String, Json> {
+    fn get(&self, name: &str) -> Option<Field<'_>> {
+        match Map::get(self, name)? {
+            Json::Number(number) => number.as_f64().map(Field::Number),
+            Json::String(string) => Some(Field::String(string)),
+            _ => None
+        }
+    }
+}
+
+// Whether `record` satisfies `search`.
+//
+// A comparison only holds if the field exists and has the same type as the literal: `age != 3` doesn't match a record
+// whose `age` is missing or is the string `"3"`. Strings are ordered by UTF-16 code units, which is what JavaScript's
+// `<` does. An empty `And` holds, and an empty `Or` or `Xor` doesn't.
+pub fn matches<R: Record + ?Sized>(search: &Search, record: &R) -> bool {
+    let mut operands = search.comparisons.iter().map(|operand| operand_matches(operand, record));
+
+    match search.join_type {
+        JoinType::And => operands.all(|result| result),
+        JoinType::Or => operands.any(|result| result),
+        // Can't short-circuit: the result flips with every operand that holds
+        JoinType::Xor => operands.filter(|result| *result).count() % 2 == 1
+    }
+}
+
+fn operand_matches<R: Record + ?Sized>(operand: &ComparisonOrSearch, record: &R) -> bool {
+    match operand {
+        ComparisonOrSearch::Comparison(comparison) => comparison_matches(comparison, record),
+        ComparisonOrSearch::Search(search) => matches(search, record)
+    }
+}
+
+pub fn comparison_matches<R: Record + ?Sized>(comparison: &Comparison, record: &R) -> bool {
+    let ordering = match (record.get(&comparison.name), &comparison.value) {
+        (Some(Field::Number(field)), Value::Number(value)) => field.partial_cmp(value),
+        (Some(Field::String(field)), Value::String(value)) => Some(field.encode_utf16().cmp(value.encode_utf16())),
+        _ => None
+    };
+    let Some(ordering) = ordering else {
+        return false;
+    };
+
+    match comparison.comparator {
+        Comparator::Equal => ordering == Ordering::Equal,
+        Comparator::NotEqual => ordering != Ordering::Equal,
+        Comparator::LessThan => ordering == Ordering::Less,
+        Comparator::GreaterThan => ordering == Ordering::Greater,
+        Comparator::LessThanOrEqual => ordering != Ordering::Greater,
+        Comparator::GreaterThanOrEqual => ordering != Ordering::Less
+    }
+}
+
+// `search` with the operands of each `And` and `Or` reordered so that `matches` settles it sooner: an `And` stops at the
+// first operand that doesn't hold, so it's best to try cheap operands that rarely hold first, and an `Or` is the other
+// way around. Which records match never changes, since evaluating an operand has no side effects and `And` and `Or`
+// don't depend on order. An `Xor` has to evaluate every operand anyway, so its order is kept (though searches inside it
+// are still reordered).
+//
+// Worth it when one search is matched against many records; spans are kept, but no longer in order.
+pub fn optimize<'a>(search: &Search<'a>) -> Search<'a> {
+    optimize_search(search).0
+}
+
+// How costly an operand is to evaluate, relative to comparing a number, and how likely it is to hold. There are no
+// statistics about the records, so these are guesses from the filter alone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Estimate {
+    cost: f64,
+    probability: f64
+}
+
+fn optimize_search<'a>(search: &Search<'a>) -> (Search<'a>, Estimate) {
+    let mut operands: Vec<_> = search.comparisons.iter().map(|operand| match operand {
+        ComparisonOrSearch::Comparison(comparison) => (operand.clone(), estimate_comparison(comparison)),
+        ComparisonOrSearch::Search(search) => {
+            let (search, estimate) = optimize_search(search);
+            (ComparisonOrSearch::Search(search), estimate)
+        }
+    }).collect();
+
+    // Cheapest per chance of settling the join first. An operand that can't settle it goes last, and ties keep the
+    // order they were written in, since the sort is stable.
+    let settles = |estimate: &Estimate| match search.join_type {
+        JoinType::And => 1.0 - estimate.probability,
+        JoinType::Or => estimate.probability,
+        JoinType::Xor => 0.0
+    };
+    if search.join_type != JoinType::Xor {
+        operands.sort_by(|(_, a), (_, b)| (a.cost / settles(a)).total_cmp(&(b.cost / settles(b))));
+    }
+
+    // Each operand is only evaluated if the ones before it didn't settle the join
+    let mut reached = 1.0;
+    let mut cost = 0.0;
+    for (_, estimate) in &operands {
+        cost += reached * estimate.cost;
+        reached *= 1.0 - settles(estimate);
+    }
+    let probabilities = operands.iter().map(|(_, estimate)| estimate.probability);
+    let probability = match search.join_type {
+        JoinType::And => probabilities.product(),
+        JoinType::Or => 1.0 - probabilities.map(|probability| 1.0 - probability).product::<f64>(),
+        // Odd parity of independent events
+        JoinType::Xor => (1.0 - probabilities.map(|probability| 1.0 - 2.0 * probability).product::<f64>()) / 2.0
+    };
+
+    let comparisons = operands.into_iter().map(|(operand, _)| operand).collect();
+    (Search { comparisons, join_type: search.join_type, id: search.id, span: search.span }, Estimate { cost, probability })
+}
+
+fn estimate_comparison(comparison: &Comparison) -> Estimate {
+    // Strings are compared a UTF-16 code unit at a time
+    let cost = match &comparison.value {
+        Value::Number(_) => 1.0,
+        Value::String(string) => 2.0 + string.len() as f64 / 16.0
+    };
+    // Most values are unequal to any given one, and a range takes about half of them
+    let probability = match comparison.comparator {
+        Comparator::Equal => 0.1,
+        Comparator::NotEqual => 0.9,
+        _ => 0.5
+    };
+    Estimate { cost, probability }
+}
+
+
+#[cfg(test)]
+mod eval_tests {
+    use serde_json::json;
+    use crate::lexer::lex;
+    use crate::parser::parse;
+    use super::*;
+
+    fn search(input: &str) -> Search<'_> {
+        let tokens = lex(input);
+        assert_eq!(tokens.1, None);
+        parse(tokens.0).unwrap()
+    }
+
+    fn record(json: Json) -> Map<String, Json> {
+        let Json::Object(record) = json else { panic!("Expected an object") };
+        record
+    }
+
+    #[test]
+    fn compares_numbers() {
+        let record = record(json!({ "age": 30 }));
+
+        assert!(matches(&search("age = 30"), &record));
+        assert!(matches(&search("age != 31"), &record));
+        assert!(matches(&search("age < 30.5"), &record));
+        assert!(matches(&search("age > -30"), &record));
+        assert!(matches(&search("age <= 30"), &record));
+        assert!(matches(&search("age >= 30"), &record));
+        assert!(!matches(&search("age > 30"), &record));
+    }
+
+    #[test]
+    fn compares_strings() {
+        let record = record(json!({ "name": "bob" }));
+
+        assert!(matches(&search("name = \"bob\""), &record));
+        assert!(matches(&search("name != \"Bob\""), &record));
+        assert!(matches(&search("name < \"carol\""), &record));
+        assert!(matches(&search("name > \"alice\""), &record));
+        assert!(!matches(&search("name < \"bob\""), &record));
+    }
+
+    #[test]
+    fn orders_strings_by_utf16_code_units() {
+        // U+FF5E sorts before U+1F600 by code point, but after it by UTF-16 code units since U+1F600 is the surrogate
+        // pair 0xD83D 0xDE00
+        let record = record(json!({ "text": "\u{FF5E}" }));
+
+        assert!(matches(&search("text > \"\u{1F600}\""), &record));
+    }
+
+    #[test]
+    fn never_matches_missing_or_mismatched_fields() {
+        let record = record(json!({ "age": "30", "empty": null }));
+
+        assert!(!matches(&search("age = 30"), &record));
+        assert!(!matches(&search("age != 30"), &record));
+        assert!(!matches(&search("missing != 1"), &record));
+        assert!(!matches(&search("empty != \"x\""), &record));
+    }
+
+    #[test]
+    fn joins_comparisons() {
+        let record = record(json!({ "a": 1, "b": 2, "c": 3 }));
+
+        assert!(matches(&search("a = 1 & b = 2"), &record));
+        assert!(!matches(&search("a = 1 & b = 3"), &record));
+        assert!(matches(&search("a = 0 | b = 2"), &record));
+        assert!(!matches(&search("a = 0 | b = 0"), &record));
+        assert!(matches(&search("a = 1 ^ b = 0"), &record));
+        assert!(!matches(&search("a = 1 ^ b = 2"), &record));
+        assert!(matches(&search("a = 1 ^ b = 2 ^ c = 3"), &record));
+        assert!(matches(&search("(a = 0 | b = 2) & c = 3"), &record));
+    }
+
+    #[test]
+    fn evaluates_empty_searches() {
+        let record = record(json!({}));
+
+        assert!(matches(&Search::new(Default::default(), JoinType::And), &record));
+        assert!(!matches(&Search::new(Default::default(), JoinType::Or), &record));
+        assert!(!matches(&Search::new(Default::default(), JoinType::Xor), &record));
+    }
+
+    #[test]
+    fn evaluates_hash_map_records() {
+        let record = HashMap::from([("age".to_string(), Value::Number(30.)), ("name".to_string(), Value::String("bob".into()))]);
+
+        assert!(matches(&search("age > 18 & name = \"bob\""), &record));
+    }
+
+    // Counts how many fields are looked up, to see how much of a search is evaluated
+    struct Counting<'r> {
+        record: &'r Map<String, Json>,
+        gets: std::cell::Cell<usize>
+    }
+
+    impl Record for Counting<'_> {
+        fn get(&self, name: &str) -> Option<Field<'_>> {
+            self.gets.set(self.gets.get() + 1);
+            Record::get(self.record, name)
+        }
+    }
+
+    fn gets(search: &Search, record: &Map<String, Json>) -> usize {
+        let counting = Counting { record, gets: Default::default() };
+        matches(search, &counting);
+        counting.gets.get()
+    }
+
+    #[test]
+    fn short_circuits_and_and_or_but_not_xor() {
+        let record = record(json!({ "a": 1, "b": 2, "c": 3 }));
+
+        assert_eq!(gets(&search("a = 0 & b = 2 & c = 3"), &record), 1);
+        assert_eq!(gets(&search("a = 1 | b = 2 | c = 3"), &record), 1);
+        assert_eq!(gets(&search("a = 1 & (b = 0 | c = 3) & a = 0"), &record), 4);
+        assert_eq!(gets(&search("a = 1 ^ b = 2 ^ c = 3"), &record), 3);
+    }
+
+    #[test]
+    fn orders_operands_by_cost_and_selectivity() {
+        // Cheap numbers before long strings, and likely to fail before likely to hold in an `And`
+        assert_eq!(optimize(&search(r#"name = "a long name to compare" & age > 30 & id = 4"#)).to_string(), r#"id = 4 & age > 30 & name = "a long name to compare""#);
+        assert_eq!(optimize(&search("a != 1 & b < 2 & c = 3")).to_string(), "c = 3 & b < 2 & a != 1");
+        // The other way around in an `Or`
+        assert_eq!(optimize(&search("a = 1 | b < 2 | c != 3")).to_string(), "c != 3 | b < 2 | a = 1");
+        // Searches inside are reordered too, and weighed by how they'd be evaluated
+        assert_eq!(optimize(&search("(a = 1 | b = 2) & c != 3 & d = 4")).to_string(), "d = 4 & (a = 1 | b = 2) & c != 3");
+        // Ties keep their order
+        assert_eq!(optimize(&search("b = 1 & a = 1")).to_string(), "b = 1 & a = 1");
+    }
+
+    #[test]
+    fn keeps_the_order_of_xor() {
+        assert_eq!(optimize(&search("a != 1 ^ b = 2 ^ (c != 3 & d = 4)")).to_string(), "a != 1 ^ b = 2 ^ (d = 4 & c != 3)");
+    }
+
+    #[test]
+    fn optimizing_makes_and_evaluate_less() {
+        let record = record(json!({ "name": "someone else", "status": "open", "age": 42 }));
+        let filter = search(r#"status != "closed" & name != "a long name to compare" & age > 90"#);
+        assert_eq!((gets(&filter, &record), gets(&optimize(&filter), &record)), (3, 1));
+    }
+}
@@ -0,0 +1,187 @@
+// Traces how a filter was evaluated against a record, so a surprising match (or miss) can be tracked down to the
+// comparisons that caused it.
+
+use serde_json::{json, Value as Json};
+use crate::eval::{comparison_matches, Field, Record};
+use crate::lexer::{JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+use crate::types::Span;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Explanation<'s, 'r> {
+    Comparison {
+        comparison: &'s Comparison<'s>,
+        // The record's value for the field, if it has one the evaluator can compare
+        actual: Option<Field<'r>>,
+        result: bool
+    },
+    Search {
+        search: &'s Search<'s>,
+        children: Vec<Explanation<'s, 'r>>,
+        // The indices of the children that decided the result: see `deciding`
+        deciding: Vec<usize>,
+        result: bool
+    }
+}
+
+impl Explanation<'_, '_> {
+    pub fn result(&self) -> bool {
+        match self {
+            Explanation::Comparison { result, .. } | Explanation::Search { result, .. } => *result
+        }
+    }
+
+    // e.g. for `age > 30 & status = "open"` against `{"age": 42}`:
+    //
+    //     {"type": "search", "join_type": "and", "result": false, "deciding": [1], "span": {"start": 0, "end": 26},
+    //      "children": [
+    //         {"type": "comparison", "name": "age", "comparator": ">", "value": 30.0, "actual": 42.0, "result": true,
+    //          "span": {"start": 0, "end": 8}},
+    //         {"type": "comparison", "name": "status", "comparator": "=", "value": "open", "actual": null,
+    //          "result": false, "span": {"start": 11, "end": 26}}
+    //     ]}
+    //
+    // Spans are in UTF-16 code units, for slicing the filter in JavaScript.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Explanation::Comparison { comparison, actual, result } => json!({
+                "type": "comparison",
+                "name": comparison.name,
+                "comparator": comparison.comparator.to_string(),
+                "value": match &comparison.value {
+                    Value::Number(number) => json!(number),
+                    Value::String(string) => json!(string)
+                },
+                "actual": match actual {
+                    Some(Field::Number(number)) => json!(number),
+                    Some(Field::String(string)) => json!(string),
+                    None => Json::Null
+                },
+                "result": result,
+                "span": span_to_json(comparison.span)
+            }),
+            Explanation::Search { search, children, deciding, result } => json!({
+                "type": "search",
+                "join_type": match search.join_type {
+                    JoinType::And => "and",
+                    JoinType::Or => "or",
+                    JoinType::Xor => "xor"
+                },
+                "result": result,
+                "children": children.iter().map(Explanation::to_json).collect::<Vec<_>>(),
+                "deciding": deciding,
+                "span": span_to_json(search.span)
+            })
+        }
+    }
+}
+
+fn span_to_json(span: Span) -> Json {
+    json!({ "start": span.start.utf16, "end": span.end.utf16 })
+}
+
+// How `search` evaluates against `record`. Every comparison is evaluated, even ones `matches` would skip, so each can be
+// shown as holding or not; the result is always the same as `matches`.
+pub fn explain<'s, 'r, R: Record + ?Sized>(search: &'s Search<'s>, record: &'r R) -> Explanation<'s, 'r> {
+    let children: Vec<_> = search.comparisons.iter().map(|operand| match operand {
+        ComparisonOrSearch::Comparison(comparison) => Explanation::Comparison {
+            comparison,
+            actual: record.get(&comparison.name),
+            result: comparison_matches(comparison, record)
+        },
+        ComparisonOrSearch::Search(search) => explain(search, record)
+    }).collect();
+
+    let results: Vec<bool> = children.iter().map(Explanation::result).collect();
+    let result = match search.join_type {
+        JoinType::And => results.iter().all(|result| *result),
+        JoinType::Or => results.iter().any(|result| *result),
+        JoinType::Xor => results.iter().filter(|result| **result).count() % 2 == 1
+    };
+
+    Explanation::Search { search, deciding: deciding(search.join_type, &results, result), children, result }
+}
+
+// The children that decided a join's result: the ones whose results agree with it, or every child of an `Xor`
+fn deciding(join_type: JoinType, results: &[bool], result: bool) -> Vec<usize> {
+    match join_type {
+        JoinType::And | JoinType::Or => (0..results.len()).filter(|&i| results[i] == result).collect(),
+        JoinType::Xor => (0..results.len()).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod explain_tests {
+    use serde_json::Map;
+    use crate::eval::matches;
+    use crate::try_parse;
+    use super::*;
+
+    fn record(json: Json) -> Map<String, Json> {
+        let Json::Object(record) = json else { panic!("Expected an object") };
+        record
+    }
+
+    #[test]
+    pub fn explains_comparisons() {
+        let search = try_parse(r#"age > 30 & status = "open""#).unwrap();
+        let record = record(json!({"age": 42, "status": ["open"]}));
+
+        assert_eq!(explain(&search, &record).to_json(), json!({
+            "type": "search", "join_type": "and", "result": false, "deciding": [1], "span": {"start": 0, "end": 26},
+            "children": [
+                {"type": "comparison", "name": "age", "comparator": ">", "value": 30.0, "actual": 42.0, "result": true, "span": {"start": 0, "end": 8}},
+                {"type": "comparison", "name": "status", "comparator": "=", "value": "open", "actual": null, "result": false, "span": {"start": 11, "end": 26}}
+            ]
+        }));
+    }
+
+    #[test]
+    pub fn finds_the_deciding_children() {
+        let cases = [
+            ("a = 1 & b = 1 & c = 1", json!({"a": 1, "b": 1, "c": 1}), true, vec![0, 1, 2]),
+            ("a = 1 & b = 1 & c = 1", json!({"a": 2, "b": 1, "c": 2}), false, vec![0, 2]),
+            ("a = 1 | b = 1 | c = 1", json!({"a": 2, "b": 1, "c": 1}), true, vec![1, 2]),
+            ("a = 1 | b = 1 | c = 1", json!({}), false, vec![0, 1, 2]),
+            ("a = 1 ^ b = 1 ^ c = 1", json!({"a": 1, "b": 1}), false, vec![0, 1, 2])
+        ];
+
+        for (filter, json, expected, expected_deciding) in cases {
+            let search = try_parse(filter).unwrap();
+            let Explanation::Search { result, deciding, .. } = explain(&search, &record(json)) else { panic!("Expected a search") };
+            assert_eq!((result, deciding), (expected, expected_deciding), "{}", filter);
+        }
+    }
+
+    #[test]
+    pub fn explains_nested_searches_with_the_same_result_as_matches() {
+        let filters = [
+            r#"(a = 1 | b = "x") & c < 3"#,
+            r#"a = 1 ^ (b = "x" & c >= 2) ^ c != 1"#,
+            r#"((a = 1))"#
+        ];
+        let records = [json!({"a": 1, "b": "x", "c": 2}), json!({"a": 2, "b": "y", "c": 1}), json!({"c": "2"}), json!({})];
+
+        for filter in filters {
+            let search = try_parse(filter).unwrap();
+            for json in &records {
+                let record = record(json.clone());
+                assert_eq!(explain(&search, &record).result(), matches(&search, &record), "{} against {}", filter, json);
+            }
+        }
+
+        let search = try_parse(r#"(a = 1 | b = "x") & c < 3"#).unwrap();
+        let record = record(json!({"a": 1, "b": "y", "c": 2}));
+        let Explanation::Search { children, .. } = explain(&search, &record) else { panic!("Expected a search") };
+        let Explanation::Search { children: or, deciding, result, .. } = &children[0] else { panic!("Expected a search") };
+        assert_eq!((*result, deciding.as_slice()), (true, [0].as_slice()));
+        assert_eq!(or[1], Explanation::Comparison { comparison: comparison(&search, 0, 1), actual: Some(Field::String("y")), result: false });
+    }
+
+    fn comparison<'s>(search: &'s Search<'s>, i: usize, j: usize) -> &'s Comparison<'s> {
+        let ComparisonOrSearch::Search(inner) = &search.comparisons[i] else { panic!("Expected a search") };
+        let ComparisonOrSearch::Comparison(comparison) = &inner.comparisons[j] else { panic!("Expected a comparison") };
+        comparison
+    }
+}
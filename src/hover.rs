@@ -0,0 +1,124 @@
+use wasm_bindgen::prelude::*;
+use crate::completion::{comparator_meaning, join_meaning, type_name};
+use crate::lexer::{lex, Token, Value};
+use crate::schema::{describe_datetime, FieldType, Schema};
+use crate::types::Span;
+
+// What to show about the token at `span` when the cursor is over it
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hover {
+    pub contents: String,
+    pub span: Span
+}
+
+// Describes the token under `cursor`, counted in UTF-16 code units as in a JavaScript string: a field's type and
+// description from `schema`, what a comparator or join means, or what a number or datetime literal was read as. Gives
+// nothing for parentheses, other strings, whitespace, and anything after the filter's first syntax error.
+pub fn hover(text: &str, cursor: usize, schema: &Schema) -> Option<Hover> {
+    let (tokens, _) = lex(text);
+    let i = tokens.iter().position(|token| token.span.start.utf16 <= cursor && cursor < token.span.end.utf16)?;
+    let token = &tokens[i];
+
+    let contents = match &token.token {
+        Token::Name(name) => match schema.field(name) {
+            Some(field_type) => {
+                let mut contents = format!("{}: {}", name, describe_type(field_type));
+                if let Some(description) = schema.description(name) {
+                    contents += "\n\n";
+                    contents += description;
+                }
+                contents
+            },
+            None => format!("{}: unknown field", name)
+        },
+        Token::Comparator(comparator) => comparator_meaning(*comparator).to_string(),
+        Token::JoinType(join_type) => join_meaning(*join_type).to_string(),
+        // e.g. `1,234.5` shows as 1234.5
+        Token::Value(Value::Number(number)) => format!("{}", number),
+        Token::Value(Value::String(value)) => {
+            // The field this value is compared with, if it's the value of a comparison
+            let field = match i.checked_sub(2).map(|j| (&tokens[j].token, &tokens[j + 1].token)) {
+                Some((Token::Name(name), Token::Comparator(_))) => schema.field(name),
+                _ => None
+            };
+            match field {
+                Some(FieldType::Datetime) => describe_datetime(value)?,
+                _ => return None
+            }
+        },
+        Token::OpenParen | Token::CloseParen => return None
+    };
+
+    Some(Hover { contents, span: token.span })
+}
+
+// e.g. "number", or "enum of `"open"`, `"closed"`"
+fn describe_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Enum(values) => format!(
+            "{} of {}",
+            type_name(field_type),
+            values.iter().map(|value| format!("`\"{}\"`", value)).collect::<Vec<_>>().join(", ")
+        ),
+        field_type => type_name(field_type).to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod hover_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_number("age");
+        schema.describe("age", "How old the user is, in years");
+        schema.add_datetime("created");
+        schema.add_enum("status", vec!["open".to_string(), "closed".to_string()]);
+        schema
+    }
+
+    // The hover at the first occurrence of `at` in `text`
+    fn contents(text: &str, at: &str) -> Option<String> {
+        hover(text, text.find(at).unwrap(), &schema()).map(|hover| hover.contents)
+    }
+
+    #[test]
+    fn describes_fields() {
+        let text = "age > 1 & status = \"open\" & size < 3";
+
+        assert_eq!(contents(text, "age").unwrap(), "age: number\n\nHow old the user is, in years");
+        assert_eq!(contents(text, "status").unwrap(), "status: enum of `\"open\"`, `\"closed\"`");
+        assert_eq!(contents(text, "size").unwrap(), "size: unknown field");
+
+        let hover = hover(text, 12, &schema()).unwrap();
+        assert_eq!(hover.span.slice(text), "status");
+    }
+
+    #[test]
+    fn describes_operators() {
+        let text = "(age >= 1 ^ age != 2)";
+
+        assert_eq!(contents(text, ">=").unwrap(), "Greater than or equal to");
+        assert_eq!(contents(text, "^").unwrap(), "Exactly one side matches");
+        assert_eq!(contents(text, "("), None);
+    }
+
+    #[test]
+    fn shows_what_literals_were_read_as() {
+        let text = "age = 1,234.5 & created < \"2024-01-31T12:00Z\" & status = \"open\" & created = \"soon\"";
+
+        assert_eq!(contents(text, "1,").unwrap(), "1234.5");
+        assert_eq!(contents(text, "\"2024").unwrap(), "Wednesday 31 January 2024, 12:00 UTC");
+        assert_eq!(contents(text, "\"open"), None);
+        assert_eq!(contents(text, "\"soon"), None);
+    }
+
+    #[test]
+    fn shows_nothing_between_tokens_or_after_errors() {
+        assert_eq!(contents("age  = 1", " "), None);
+        assert_eq!(contents("age = 1 @ age = 2", "age = 2"), None);
+        assert_eq!(hover("age", 3, &schema()), None);
+    }
+}
@@ -0,0 +1,400 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use crate::lexer::{Comparator, JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+
+// Reads a Lucene query string such as `status:open AND (priority:>2 OR -assignee:"me")` into a `Search`.
+//
+// Supported are `field:value` and `field:"quoted value"`, the `field:>value`/`>=`/`<`/`<=` shorthands, ranges like
+// `field:[1 TO 5]`, `field:{1 TO *}` and `field:[a TO 5}`, the `AND`/`&&`, `OR`/`||` and `NOT`/`!` operators, the `+`
+// and `-` prefixes, and parentheses. Clauses written next to each other without an operator follow Lucene's rules: if
+// any is required (`+`), only the required and prohibited (`-`) clauses count, otherwise at least one of the optional
+// clauses must hold. Negated groups are pushed down onto their comparisons with De Morgan's laws. Terms without a field,
+// wildcards, fuzzy/proximity searches and boosts have no equivalent in a filter and are rejected.
+pub fn from_lucene(query: &str) -> Result<Search<'static>, String> {
+    let mut parser = LuceneParser { s: query.chars().peekable() };
+
+    let result = parser.parse_or()?;
+    if let Some(c) = parser.peek_non_whitespace() {
+        return Err(format!("Unexpected `{}`", c));
+    }
+
+    Ok(into_search(result))
+}
+
+// Reads a URL query string such as `?status=open&priority_gte=2` into a `Search` that requires every parameter.
+//
+// A parameter's name may end in `_eq`, `_ne`, `_lt`, `_lte`, `_gt` or `_gte` to pick the comparator; it's `=` otherwise.
+// Repeating the same `=` parameter (`status=open&status=new`) matches any of its values. Names and values are
+// percent-decoded, and values that look like numbers are compared as numbers.
+pub fn from_query_string(query: &str) -> Result<Search<'static>, String> {
+    let query = query.strip_prefix('?').unwrap_or(query);
+    let mut groups: Vec<Vec<Comparison<'static>>> = Vec::new();
+
+    for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+        let Some((key, value)) = parameter.split_once('=') else {
+            return Err(format!("Expected a value for parameter `{}`", percent_decode(parameter)?));
+        };
+        let key = percent_decode(key)?;
+        let value = parse_value(&percent_decode(value)?);
+
+        let (name, comparator) = [
+            ("_eq", Comparator::Equal),
+            ("_ne", Comparator::NotEqual),
+            ("_lt", Comparator::LessThan),
+            ("_lte", Comparator::LessThanOrEqual),
+            ("_gt", Comparator::GreaterThan),
+            ("_gte", Comparator::GreaterThanOrEqual)
+        ].into_iter()
+            .find_map(|(suffix, comparator)| Some((key.strip_suffix(suffix).filter(|name| !name.is_empty())?, comparator)))
+            .unwrap_or((key.as_str(), Comparator::Equal));
+        let comparison = comparison(name, comparator, value)?;
+
+        let group = groups.iter_mut().find(|group| {
+            comparison.comparator == Comparator::Equal && group[0].comparator == Comparator::Equal && group[0].name == comparison.name
+        });
+        match group {
+            Some(group) => group.push(comparison),
+            None => groups.push(vec![comparison])
+        }
+    }
+
+    let operands = groups.into_iter()
+        .map(|group| join(JoinType::Or, group.into_iter().map(ComparisonOrSearch::Comparison).collect()))
+        .collect();
+    Ok(into_search(join(JoinType::And, operands)))
+}
+
+struct LuceneParser<'a> {
+    s: Peekable<Chars<'a>>
+}
+
+// Whether a clause must, must not, or may hold when written next to other clauses
+#[derive(Clone, Copy, PartialEq)]
+enum Occur {
+    Must,
+    MustNot,
+    Should
+}
+
+impl LuceneParser<'_> {
+    fn parse_or(&mut self) -> Result<ComparisonOrSearch<'static>, String> {
+        let mut operands = vec![self.parse_and()?];
+        while self.eat_operator("OR", "||") {
+            operands.push(self.parse_and()?);
+        }
+        Ok(join(JoinType::Or, operands))
+    }
+
+    fn parse_and(&mut self) -> Result<ComparisonOrSearch<'static>, String> {
+        let mut operands = vec![self.parse_clauses()?];
+        while self.eat_operator("AND", "&&") {
+            operands.push(self.parse_clauses()?);
+        }
+        Ok(join(JoinType::And, operands))
+    }
+
+    // Parses clauses written next to each other without an operator
+    fn parse_clauses(&mut self) -> Result<ComparisonOrSearch<'static>, String> {
+        let mut clauses = Vec::new();
+        loop {
+            match self.peek_non_whitespace() {
+                None | Some(')') => break,
+                _ if !clauses.is_empty() && (self.at_keyword("AND") || self.at_keyword("OR") || self.at("&&") || self.at("||")) => break,
+                _ => clauses.push(self.parse_clause()?)
+            }
+        }
+
+        if clauses.is_empty() {
+            return match self.peek_non_whitespace() {
+                None => Err("Unexpected end of query".to_string()),
+                Some(c) => Err(format!("Unexpected `{}`", c))
+            };
+        }
+
+        let has_required = clauses.iter().any(|(occur, _)| *occur == Occur::Must);
+        let mut required = Vec::new();
+        let mut optional = Vec::new();
+        for (occur, clause) in clauses {
+            match occur {
+                Occur::Must => required.push(clause),
+                Occur::MustNot => required.push(negate(clause)),
+                Occur::Should => optional.push(clause)
+            }
+        }
+        if !has_required && !optional.is_empty() {
+            required.push(join(JoinType::Or, optional));
+        }
+
+        Ok(join(JoinType::And, required))
+    }
+
+    fn parse_clause(&mut self) -> Result<(Occur, ComparisonOrSearch<'static>), String> {
+        let occur = if self.eat("+") {
+            Occur::Must
+        }
+        else if self.eat("-") || self.eat("!") || self.eat_keyword("NOT") {
+            Occur::MustNot
+        }
+        else {
+            Occur::Should
+        };
+
+        if self.eat("(") {
+            let group = self.parse_or()?;
+            if !self.eat(")") {
+                return Err("Expected `)`".to_string());
+            }
+            return Ok((occur, group));
+        }
+
+        let field = self.parse_word()?;
+        if !self.eat_adjacent(':') {
+            return Err(format!("Expected `field:value` but found `{}` without a field", field));
+        }
+
+        let clause = if self.eat_adjacent('[') || self.peek_is('{') {
+            let lower_inclusive = !self.eat_adjacent('{');
+            let lower = self.parse_word()?;
+            if !self.eat_keyword("TO") {
+                return Err("Expected `TO` in range".to_string());
+            }
+            let upper = self.parse_word()?;
+            let upper_inclusive = match self.peek_non_whitespace() {
+                Some(']') => true,
+                Some('}') => false,
+                _ => return Err("Expected `]` or `}` to close range".to_string())
+            };
+            self.s.next();
+
+            let mut bounds = Vec::new();
+            if lower != "*" {
+                let comparator = if lower_inclusive { Comparator::GreaterThanOrEqual } else { Comparator::GreaterThan };
+                bounds.push(ComparisonOrSearch::Comparison(comparison(&field, comparator, parse_value(&lower))?));
+            }
+            if upper != "*" {
+                let comparator = if upper_inclusive { Comparator::LessThanOrEqual } else { Comparator::LessThan };
+                bounds.push(ComparisonOrSearch::Comparison(comparison(&field, comparator, parse_value(&upper))?));
+            }
+            if bounds.is_empty() {
+                return Err(format!("Range on `{}` has no bounds", field));
+            }
+            join(JoinType::And, bounds)
+        }
+        else {
+            let comparator = if self.eat_adjacent('>') {
+                if self.eat_adjacent('=') { Comparator::GreaterThanOrEqual } else { Comparator::GreaterThan }
+            }
+            else if self.eat_adjacent('<') {
+                if self.eat_adjacent('=') { Comparator::LessThanOrEqual } else { Comparator::LessThan }
+            }
+            else {
+                Comparator::Equal
+            };
+
+            let quoted = self.peek_is('"');
+            let value = self.parse_word()?;
+            if !quoted && (value == "*" || value.contains(['*', '?'])) {
+                return Err(format!("Wildcard value `{}` on `{}` isn't supported", value, field));
+            }
+            let value = if quoted { Value::String(value.into()) } else { parse_value(&value) };
+            ComparisonOrSearch::Comparison(comparison(&field, comparator, value)?)
+        };
+
+        if matches!(self.s.peek(), Some('~' | '^')) {
+            return Err(format!("Fuzzy, proximity and boost modifiers on `{}` aren't supported", field));
+        }
+
+        Ok((occur, clause))
+    }
+
+    // Reads a quoted string, or a bare word up to whitespace or a special character, resolving `\` escapes
+    fn parse_word(&mut self) -> Result<String, String> {
+        self.peek_non_whitespace();
+        let mut word = String::new();
+
+        if self.eat_adjacent('"') {
+            loop {
+                match self.s.next() {
+                    None => return Err("Unclosed `\"`".to_string()),
+                    Some('"') => return Ok(word),
+                    Some('\\') => word.extend(self.s.next()),
+                    Some(c) => word.push(c)
+                }
+            }
+        }
+
+        while let Some(&c) = self.s.peek() {
+            match c {
+                '\\' => {
+                    self.s.next();
+                    word.extend(self.s.next());
+                    continue;
+                },
+                c if c.is_whitespace() || "()[]{}:\"~^".contains(c) => break,
+                _ => word.push(c)
+            }
+            self.s.next();
+        }
+
+        if word.is_empty() {
+            return match self.s.peek() {
+                None => Err("Unexpected end of query".to_string()),
+                Some(c) => Err(format!("Unexpected `{}`", c))
+            };
+        }
+        Ok(word)
+    }
+
+    fn peek_non_whitespace(&mut self) -> Option<char> {
+        while self.s.next_if(|c| c.is_whitespace()).is_some() { }
+        self.s.peek().copied()
+    }
+
+    fn peek_is(&mut self, c: char) -> bool {
+        self.s.peek() == Some(&c)
+    }
+
+    fn at(&mut self, text: &str) -> bool {
+        self.peek_non_whitespace();
+        let mut ahead = self.s.clone();
+        text.chars().all(|c| ahead.next() == Some(c))
+    }
+
+    // A keyword must be followed by whitespace or a parentheses so that e.g. a field named `ORDER` isn't read as `OR`
+    fn at_keyword(&mut self, keyword: &str) -> bool {
+        self.at(keyword) && self.s.clone().nth(keyword.len()).is_none_or(|c| c.is_whitespace() || c == '(')
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if !self.at(text) {
+            return false;
+        }
+        for _ in text.chars() {
+            self.s.next();
+        }
+        true
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.at_keyword(keyword) && self.eat(keyword)
+    }
+
+    fn eat_operator(&mut self, keyword: &str, symbol: &str) -> bool {
+        self.eat_keyword(keyword) || self.eat(symbol)
+    }
+
+    // Unlike `eat`, doesn't skip whitespace first, for syntax that must directly follow the previous character
+    fn eat_adjacent(&mut self, c: char) -> bool {
+        self.s.next_if_eq(&c).is_some()
+    }
+}
+
+fn comparison(name: &str, comparator: Comparator, value: Value<'static>) -> Result<Comparison<'static>, String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("Field `{}` can't be written in filter syntax", name));
+    }
+    // Filter strings have no escapes, so there's no way to write a `"` inside one
+    if let Value::String(string) = &value {
+        if string.contains('"') {
+            return Err(format!("Value `{}` on `{}` contains a `\"`, which can't be written in filter syntax", string, name));
+        }
+    }
+
+    Ok(Comparison::new(name.to_string().into(), comparator, value))
+}
+
+// Reads `value` as a number if the filter syntax would, otherwise as a string
+fn parse_value(value: &str) -> Value<'static> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let is_number = !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.chars().filter(|c| *c == '.').count() <= 1
+        && digits != ".";
+
+    match value.parse::<f64>() {
+        Ok(number) if is_number => Value::Number(number),
+        _ => Value::String(value.to_string().into())
+    }
+}
+
+// Combines `operands` with `join_type`, flattening nested joins of the same type the way the parser's `merge_subtree`
+// does, so imported filters have the same shape as parsed ones
+fn join(join_type: JoinType, operands: Vec<ComparisonOrSearch<'static>>) -> ComparisonOrSearch<'static> {
+    let mut comparisons = Vec::new();
+    for operand in operands {
+        match operand {
+            ComparisonOrSearch::Search(mut search) if search.join_type == join_type => comparisons.append(&mut search.comparisons),
+            operand => comparisons.push(operand)
+        }
+    }
+
+    if comparisons.len() == 1 {
+        return comparisons.pop().unwrap();
+    }
+    ComparisonOrSearch::Search(Search::new(comparisons, join_type))
+}
+
+fn negate(operand: ComparisonOrSearch<'static>) -> ComparisonOrSearch<'static> {
+    match operand {
+        ComparisonOrSearch::Comparison(comparison) => ComparisonOrSearch::Comparison(Comparison {
+            comparator: match comparison.comparator {
+                Comparator::Equal => Comparator::NotEqual,
+                Comparator::NotEqual => Comparator::Equal,
+                Comparator::LessThan => Comparator::GreaterThanOrEqual,
+                Comparator::GreaterThan => Comparator::LessThanOrEqual,
+                Comparator::LessThanOrEqual => Comparator::GreaterThan,
+                Comparator::GreaterThanOrEqual => Comparator::LessThan
+            },
+            ..comparison
+        }),
+
+        ComparisonOrSearch::Search(search) => {
+            let join_type = match search.join_type {
+                JoinType::And => JoinType::Or,
+                JoinType::Or => JoinType::And,
+                // `!(a ^ b)` is `!a ^ b`, so only the first operand needs negating
+                JoinType::Xor => {
+                    let mut comparisons = search.comparisons.into_iter();
+                    let first = comparisons.next().map(negate);
+                    return ComparisonOrSearch::Search(Search::new(first.into_iter().chain(comparisons).collect(), JoinType::Xor));
+                }
+            };
+            join(join_type, search.comparisons.into_iter().map(negate).collect())
+        }
+    }
+}
+
+// Numbered like a parsed filter's
+fn into_search(operand: ComparisonOrSearch<'static>) -> Search<'static> {
+    let mut search = match operand {
+        ComparisonOrSearch::Search(search) => search,
+        comparison => Search::new(vec![comparison], JoinType::And)
+    };
+    search.number_nodes();
+    search
+}
+
+fn percent_decode(s: &str) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    let mut s = s.bytes();
+
+    while let Some(byte) = s.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [s.next(), s.next()];
+                let decoded = match hex {
+                    [Some(high), Some(low)] => std::str::from_utf8(&[high, low]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                    _ => None
+                };
+                bytes.push(decoded.ok_or("Invalid percent-encoding in query string")?);
+            },
+            byte => bytes.push(byte)
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| "Query string isn't valid UTF-8".to_string())
+}
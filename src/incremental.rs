@@ -0,0 +1,242 @@
+use wasm_bindgen::prelude::*;
+use crate::lexer::{BareToken, BareTokenData, Lexer};
+use crate::types::{FilterError, Position};
+use crate::utils;
+
+// Keeps the tokens of a filter up to date as it's edited, for the live editor. An edit is re-lexed from the token it
+// touches until the new tokens line up with the old ones again, and the tokens after that are kept and shifted, so
+// typing in a long filter only costs as much as lexing the tokens around the edit.
+//
+// The tokens and errors are always the same as `lex_filter` would give for the current text.
+#[wasm_bindgen]
+pub struct IncrementalLexer {
+    text: String,
+    tokens: Vec<BareTokenData>,
+    errors: Vec<FilterError>    // One for each `BareToken::Error` token, in the same order
+}
+
+// What an edit changed: the old tokens `start..start + deleted` were replaced with `tokens`. The tokens after them are
+// the same as before, shifted to their new positions.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenDelta {
+    pub start: usize,
+    pub deleted: usize,
+    pub tokens: Vec<BareTokenData>
+}
+
+#[wasm_bindgen]
+impl IncrementalLexer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(text: &str) -> Self {
+        utils::set_panic_hook();
+
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        relex(Lexer::new(text), &mut tokens, &mut errors, |_| false);
+
+        Self { text: text.to_string(), tokens, errors }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tokens(&self) -> Vec<BareTokenData> {
+        self.tokens.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn errors(&self) -> Vec<FilterError> {
+        self.errors.clone()
+    }
+
+    // Replaces `deleted` characters at `offset` with `inserted`. Both are counted in UTF-16 code units, as in a
+    // JavaScript string.
+    pub fn edit(&mut self, offset: usize, deleted: usize, inserted: &str) -> Result<TokenDelta, String> {
+        // A token can only be changed by an edit inside it or right after it (e.g. typing at the end of a name), so
+        // lexing can restart after the last token that ends before the edit
+        let first = self.tokens.partition_point(|token| token.span.end.utf16 < offset);
+        let restart = if first == 0 { Position::default() } else { self.tokens[first - 1].span.end };
+
+        let edit_start = advance(&self.text, restart, offset)
+            .ok_or_else(|| format!("Edit offset {} isn't a character boundary in the filter", offset))?;
+        let old_end = advance(&self.text, edit_start, offset + deleted)
+            .ok_or_else(|| format!("Can't delete {} code units at offset {}", deleted, offset))?;
+        let new_end = inserted.chars().fold(edit_start, Position::after);
+
+        self.text.replace_range(edit_start.byte..old_end.byte, inserted);
+
+        // Once a new token starts where an old one did after the edit, the text from there on is unchanged, so lexing
+        // it again would give the same tokens
+        let mut last = first;
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        let old_tokens = &self.tokens;
+        let resynced = relex(Lexer::starting_at(&self.text, restart), &mut tokens, &mut errors, |start| {
+            if start.byte < new_end.byte {
+                return false;
+            }
+            let old_start = start.byte - new_end.byte + old_end.byte;
+            while last < old_tokens.len() && old_tokens[last].span.start.byte < old_start {
+                last += 1;
+            }
+            last < old_tokens.len() && old_tokens[last].span.start.byte == old_start
+        });
+        if !resynced {
+            last = self.tokens.len();
+        }
+
+        for token in &mut self.tokens[last..] {
+            token.span.start = shift(token.span.start, old_end, new_end);
+            token.span.end = shift(token.span.end, old_end, new_end);
+        }
+        let first_error = count_errors(&self.tokens[..first]);
+        let deleted_errors = count_errors(&self.tokens[first..last]);
+        for error in &mut self.errors[first_error + deleted_errors..] {
+            error.span.start = shift(error.span.start, old_end, new_end);
+            error.span.end = shift(error.span.end, old_end, new_end);
+            error.range.start = shift(error.range.start, old_end, new_end);
+            error.range.end = shift(error.range.end, old_end, new_end);
+            for fix in &mut error.fixes {
+                fix.span.start = shift(fix.span.start, old_end, new_end);
+                fix.span.end = shift(fix.span.end, old_end, new_end);
+            }
+        }
+
+        self.tokens.splice(first..last, tokens.iter().cloned());
+        self.errors.splice(first_error..first_error + deleted_errors, errors);
+
+        Ok(TokenDelta { start: first, deleted: last - first, tokens })
+    }
+}
+
+// Lexes tokens into `tokens` and `errors` until `stop` returns true for the start of the next token. Returns whether it
+// stopped before the end of the filter.
+fn relex(lexer: Lexer, tokens: &mut Vec<BareTokenData>, errors: &mut Vec<FilterError>, mut stop: impl FnMut(Position) -> bool) -> bool {
+    for result in lexer {
+        let token = match &result {
+            Ok(token) => token.to_bare(),
+            Err(error) => BareTokenData { token: BareToken::Error, span: error.range }
+        };
+        if stop(token.span.start) {
+            return true;
+        }
+
+        tokens.push(token);
+        if let Err(error) = result {
+            errors.push(error);
+        }
+    }
+
+    false
+}
+
+// The position `utf16` code units into `text`, starting from `position`, if that's a character boundary
+fn advance(text: &str, mut position: Position, utf16: usize) -> Option<Position> {
+    let mut chars = text[position.byte..].chars();
+    while position.utf16 < utf16 {
+        position = position.after(chars.next()?);
+    }
+
+    (position.utf16 == utf16).then_some(position)
+}
+
+// Where `position`, which came at or after the end of the replaced text `old_end`, is after the edit
+fn shift(position: Position, old_end: Position, new_end: Position) -> Position {
+    Position {
+        byte: position.byte - old_end.byte + new_end.byte,
+        char: position.char - old_end.char + new_end.char,
+        utf16: position.utf16 - old_end.utf16 + new_end.utf16,
+        line: position.line - old_end.line + new_end.line,
+        // Only the rest of the edit's last line moves sideways
+        col: if position.line == old_end.line { position.col - old_end.col + new_end.col } else { position.col }
+    }
+}
+
+fn count_errors(tokens: &[BareTokenData]) -> usize {
+    tokens.iter().filter(|token| token.token == BareToken::Error).count()
+}
+
+
+#[cfg(test)]
+mod incremental_tests {
+    use super::*;
+
+    // Applies the edit, and checks the result is the same as lexing the new text from scratch
+    fn edit(lexer: &mut IncrementalLexer, offset: usize, deleted: usize, inserted: &str) -> TokenDelta {
+        let delta = lexer.edit(offset, deleted, inserted).unwrap();
+
+        let expected = IncrementalLexer::new(&lexer.text);
+        assert_eq!(lexer.tokens, expected.tokens, "after editing to {:?}", lexer.text);
+        assert_eq!(lexer.errors, expected.errors, "after editing to {:?}", lexer.text);
+        delta
+    }
+
+    #[test]
+    fn relexes_only_the_edited_token() {
+        let mut lexer = IncrementalLexer::new("a = 1 & b = 2 & c = 3");
+
+        let delta = edit(&mut lexer, 8, 1, "bb");
+
+        assert_eq!(lexer.text, "a = 1 & bb = 2 & c = 3");
+        assert_eq!((delta.start, delta.deleted, delta.tokens.len()), (4, 1, 1));
+        assert_eq!(delta.tokens[0].span.start.byte, 8);
+        assert_eq!(delta.tokens[0].span.end.byte, 10);
+    }
+
+    #[test]
+    fn extends_the_token_before_the_edit() {
+        let mut lexer = IncrementalLexer::new("name = 1");
+
+        let delta = edit(&mut lexer, 4, 0, "_2");
+
+        assert_eq!((delta.start, delta.deleted, delta.tokens.len()), (0, 1, 1));
+        assert_eq!(delta.tokens[0].span.end.byte, 6);
+    }
+
+    #[test]
+    fn relexes_to_the_end_when_a_string_is_opened() {
+        let mut lexer = IncrementalLexer::new("a = 1 & b = \"x\" & c = 3");
+
+        let delta = edit(&mut lexer, 4, 0, "\"");
+
+        assert_eq!((delta.start, delta.deleted), (2, 9));
+        assert_eq!(delta.tokens.len(), 3);
+    }
+
+    #[test]
+    fn shifts_tokens_and_errors_after_the_edit() {
+        let mut lexer = IncrementalLexer::new("a = 1 &\nb @ 2 & c = 😀");
+
+        let delta = edit(&mut lexer, 0, 0, "z = \"日本\" |\n");
+
+        assert_eq!((delta.start, delta.deleted, delta.tokens.len()), (0, 0, 4));
+        assert_eq!(lexer.errors.len(), 2);
+        assert_eq!(lexer.errors[0].span.start, Position { byte: 25, char: 21, utf16: 21, line: 2, col: 2 });
+    }
+
+    #[test]
+    fn handles_edits_at_the_ends() {
+        let mut lexer = IncrementalLexer::new("");
+
+        edit(&mut lexer, 0, 0, "a = 1");
+        edit(&mut lexer, 5, 0, "0 | b = 2");
+        edit(&mut lexer, 0, 0, "(");
+        edit(&mut lexer, 15, 0, ")");
+        edit(&mut lexer, 0, 16, "");
+
+        assert!(lexer.tokens.is_empty());
+    }
+
+    #[test]
+    fn rejects_edits_outside_the_text() {
+        let mut lexer = IncrementalLexer::new("a = \"😀\"");
+
+        assert!(lexer.edit(6, 0, "x").is_err());
+        assert!(lexer.edit(5, 10, "").is_err());
+        assert!(lexer.edit(12, 0, "x").is_err());
+    }
+}
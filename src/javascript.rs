@@ -0,0 +1,140 @@
+use std::fmt::Write;
+use crate::lexer::{Comparator, JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+
+// Generates the source of a JavaScript arrow function, e.g. `(row) => (typeof row["a"] === "string" && row["a"] === "x")`,
+// that returns whether a row satisfies `search`. It has the same semantics as `eval::matches`, so it can be compiled
+// once with `new Function` and run over many rows.
+//
+// Field names and string values are emitted as JSON string literals, which are always valid JavaScript strings, so
+// filter text can't break out of the generated code.
+pub fn to_javascript(search: &Search) -> String {
+    let mut js = String::from("(row) => ");
+    write_search(&mut js, search);
+    js
+}
+
+fn write_search(js: &mut String, search: &Search) {
+    if search.comparisons.is_empty() {
+        js.push_str(if search.join_type == JoinType::And { "true" } else { "false" });
+        return;
+    }
+
+    let separator = match search.join_type {
+        JoinType::Or => " || ",
+        JoinType::And => " && ",
+        // Operands are always booleans, and comparing each with the parity so far gives the parity of them all
+        JoinType::Xor => " !== "
+    };
+
+    if search.join_type == JoinType::Xor {
+        js.push_str(&"(".repeat(search.comparisons.len() - 1));
+    }
+    for (i, operand) in search.comparisons.iter().enumerate() {
+        if i > 0 {
+            js.push_str(separator);
+        }
+        write_operand(js, operand);
+        if search.join_type == JoinType::Xor && i > 0 {
+            js.push(')');
+        }
+    }
+}
+
+fn write_operand(js: &mut String, operand: &ComparisonOrSearch) {
+    match operand {
+        ComparisonOrSearch::Comparison(comparison) => write_comparison(js, comparison),
+        ComparisonOrSearch::Search(search) => {
+            js.push('(');
+            write_search(js, search);
+            js.push(')');
+        }
+    }
+}
+
+fn write_comparison(js: &mut String, comparison: &Comparison) {
+    let field = format!("row[{}]", string_literal(&comparison.name));
+    let (kind, value) = match &comparison.value {
+        Value::Number(number) => ("number", number_literal(*number)),
+        Value::String(string) => ("string", string_literal(string))
+    };
+    let operator = match comparison.comparator {
+        Comparator::Equal => "===",
+        Comparator::NotEqual => "!==",
+        Comparator::LessThan => "<",
+        Comparator::GreaterThan => ">",
+        Comparator::LessThanOrEqual => "<=",
+        Comparator::GreaterThanOrEqual => ">="
+    };
+
+    // Checking the type first makes missing fields and mismatched types never match, like `eval::matches`
+    write!(js, "(typeof {} === \"{}\" && {} {} {})", field, kind, field, operator, value).expect("Writing to a String can't fail");
+}
+
+// Numbers too long for an `f64` lex as infinite, which Rust would write as `inf`, a name JavaScript doesn't know
+fn number_literal(number: f64) -> String {
+    match number {
+        f64::INFINITY => "Infinity".to_string(),
+        f64::NEG_INFINITY => "-Infinity".to_string(),
+        number if number.is_nan() => "NaN".to_string(),
+        number => number.to_string()
+    }
+}
+
+fn string_literal(string: &str) -> String {
+    serde_json::to_string(string).expect("Serializing a string can't fail")
+}
+
+
+#[cfg(test)]
+mod javascript_tests {
+    use crate::lexer::lex;
+    use crate::parser::parse;
+    use super::*;
+
+    fn generate(input: &str) -> String {
+        let tokens = lex(input);
+        assert_eq!(tokens.1, None);
+        to_javascript(&parse(tokens.0).unwrap())
+    }
+
+    #[test]
+    fn generates_every_comparator() {
+        assert_eq!(generate("a = 1"), "(row) => (typeof row[\"a\"] === \"number\" && row[\"a\"] === 1)");
+        assert_eq!(generate("a != \"x\""), "(row) => (typeof row[\"a\"] === \"string\" && row[\"a\"] !== \"x\")");
+        assert_eq!(generate("a < -2.5"), "(row) => (typeof row[\"a\"] === \"number\" && row[\"a\"] < -2.5)");
+        assert_eq!(generate("a > 1,000"), "(row) => (typeof row[\"a\"] === \"number\" && row[\"a\"] > 1000)");
+        assert_eq!(generate("a <= .5"), "(row) => (typeof row[\"a\"] === \"number\" && row[\"a\"] <= 0.5)");
+        assert_eq!(generate("a >= \"m\""), "(row) => (typeof row[\"a\"] === \"string\" && row[\"a\"] >= \"m\")");
+    }
+
+    #[test]
+    fn generates_joins() {
+        assert_eq!(
+            generate("a = 1 & (b = 2 | c = 3)"),
+            "(row) => (typeof row[\"a\"] === \"number\" && row[\"a\"] === 1) && ((typeof row[\"b\"] === \"number\" && row[\"b\"] === 2) || (typeof row[\"c\"] === \"number\" && row[\"c\"] === 3))"
+        );
+    }
+
+    #[test]
+    fn generates_xor_as_parity() {
+        assert_eq!(
+            generate("a = 1 ^ b = 2 ^ c = 3"),
+            "(row) => (((typeof row[\"a\"] === \"number\" && row[\"a\"] === 1) !== (typeof row[\"b\"] === \"number\" && row[\"b\"] === 2)) !== (typeof row[\"c\"] === \"number\" && row[\"c\"] === 3))"
+        );
+    }
+
+    #[test]
+    fn escapes_string_values() {
+        assert_eq!(
+            generate("a = \"\\\n</script>\u{2028}\""),
+            "(row) => (typeof row[\"a\"] === \"string\" && row[\"a\"] === \"\\\\\\n</script>\u{2028}\")"
+        );
+    }
+
+    #[test]
+    fn generates_empty_searches() {
+        assert_eq!(to_javascript(&Search::new(Vec::new(), JoinType::And)), "(row) => true");
+        assert_eq!(to_javascript(&Search::new(Vec::new(), JoinType::Or)), "(row) => false");
+    }
+}
@@ -1,11 +1,11 @@
-use std::collections::LinkedList;
+use std::borrow::Cow;
 use std::fmt::Debug;
-use std::iter::Peekable;
-use std::str::Chars;
+use std::iter::FusedIterator;
 use wasm_bindgen::prelude::*;
-use crate::types::FilterError;
+use crate::types::{FilterError, Fix, Position, Span};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum Comparator {
     Equal,
     NotEqual,
@@ -15,13 +15,17 @@ pub enum Comparator {
     GreaterThanOrEqual
 }
 
-#[derive(Debug, PartialEq)]
-pub enum Value {
+// Strings borrow from the filter text when lexed, but can be owned when a filter is built some other way (e.g. imported
+// or deserialized)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(untagged))]
+pub enum Value<'a> {
     Number(f64),
-    String(String)
+    String(Cow<'a, str>)
 }
 
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum JoinType {
     Or,
     And,
@@ -30,71 +34,45 @@ pub enum JoinType {
 }
 
 #[derive(Debug, PartialEq)]
-pub enum Token {
-    Name(String),
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum Token<'a> {
+    Name(Cow<'a, str>),
     Comparator(Comparator),
-    Value(Value),
+    Value(Value<'a>),
     JoinType(JoinType),
     OpenParen,
     CloseParen
 }
 
 #[derive(Debug, PartialEq)]
-pub struct TokenData {
-    pub token: Token,
-    pub source: String,
-    pub start: usize,       // 0-indexed, inclusive
-    pub start_line: usize,  // 0-indexed, inclusive
-    pub start_col: usize,   // 0-indexed, inclusive
-    pub end: usize,         // 0-indexed, not inclusive
-    pub end_line: usize,    // 0-indexed, inclusive
-    pub end_col: usize,     // 0-indexed, not inclusive
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenData<'a> {
+    pub token: Token<'a>,
+    pub source: Cow<'a, str>,
+    pub span: Span
 }
 
-impl TokenData {
+impl<'a> TokenData<'a> {
     pub fn to_bare(&self) -> BareTokenData {
-        match &self.token {
-            Token::Name(_) =>
-                BareTokenData{ token: BareToken::Name, start: self.start, start_line: self.start_line, start_col: self.start_col, end: self.end, end_line: self.end_line, end_col: self.end_col },
-
-            Token::Comparator(_) =>
-                BareTokenData{ token: BareToken::Comparator, start: self.start, start_line: self.start_line, start_col: self.start_col, end: self.end, end_line: self.end_line, end_col: self.end_col },
-            
-            Token::Value(Value::String(_)) =>
-                BareTokenData{ token: BareToken::String, start: self.start, start_line: self.start_line, start_col: self.start_col, end: self.end, end_line: self.end_line, end_col: self.end_col },
-
-            Token::Value(Value::Number(_)) =>
-                BareTokenData{ token: BareToken::Number, start: self.start, start_line: self.start_line, start_col: self.start_col, end: self.end, end_line: self.end_line, end_col: self.end_col },
-
-            Token::JoinType(_) =>
-                BareTokenData{ token: BareToken::JoinType, start: self.start, start_line: self.start_line, start_col: self.start_col, end: self.end, end_line: self.end_line, end_col: self.end_col },
-
-            _ =>
-                BareTokenData{ token: BareToken::Paren, start: self.start, start_line: self.start_line, start_col: self.start_col, end: self.end, end_line: self.end_line, end_col: self.end_col }
-
-        }
+        let token = match &self.token {
+            Token::Name(_) => BareToken::Name,
+            Token::Comparator(_) => BareToken::Comparator,
+            Token::Value(Value::String(_)) => BareToken::String,
+            Token::Value(Value::Number(_)) => BareToken::Number,
+            Token::JoinType(_) => BareToken::JoinType,
+            Token::OpenParen | Token::CloseParen => BareToken::Paren
+        };
+
+        BareTokenData { token, span: self.span }
     }
     
-    pub fn new(token: Token, source: String, start: usize, start_line: usize, start_col: usize, end: usize, end_line: usize, end_col: usize) -> Self {
+    pub fn new(token: Token<'a>, source: Cow<'a, str>, span: Span) -> Self {
         Self {
             token,
             source,
-            start,
-            start_line,
-            start_col,
-            end,
-            end_line,
-            end_col
+            span
         }
     }
-    
-    pub fn new_oneline(token: Token, source: String, line: usize, start: usize, start_col: usize, end: usize, end_col: usize) -> Self {
-        Self::new(token, source, start, line, start_col, end, line, end_col)
-    }
-    
-    pub fn new_onechar(token: Token, source: String, line: usize, start: usize, start_col: usize) -> Self {
-        Self::new_oneline(token, source, line, start, start_col, start + 1, start_col + 1)
-    }
 }
 
 #[wasm_bindgen]
@@ -113,210 +91,226 @@ pub enum BareToken {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BareTokenData {
     pub token: BareToken,
-    pub start: usize,       // 0-indexed, inclusive
-    pub start_line: usize,  // 0-indexed, inclusive
-    pub start_col: usize,   // 0-indexed, inclusive
-    pub end: usize,         // 0-indexed, not inclusive
-    pub end_line: usize,    // 0-indexed, inclusive
-    pub end_col: usize,     // 0-indexed, not inclusive
+    pub span: Span
 }
 
-pub fn lex(mut s: &mut Peekable<Chars>, mut cursor: usize, mut line: usize, mut col: usize) -> (LinkedList<TokenData>, Option<FilterError>) {
-    let mut tokens = LinkedList::new();
-
-    while let Some(c) = s.next() {
-        match c {
-            '"' => tokens.push_back(lex_string(&mut s, &mut cursor, &mut line, &mut col)),
-            'a'..='z' | 'A'..='Z' | '_' => tokens.push_back(lex_name(c, &mut s, &mut cursor, line, &mut col)),
-            '0'..='9' | '-' | '.' => {
-                let result = lex_number(c, &mut s, &mut cursor, line, &mut col);
-                match result {
-                    Ok(token) => tokens.push_back(token),
-                    Err(error) => return (tokens, Some(error))
-                }
-            },
-            '<' | '>' | '=' | '!' => {
-                let result = lex_comparator(c, &mut s, &mut cursor, line, &mut col);
-                match result {
-                    Ok(token) => tokens.push_back(token),
-                    Err(error) => return (tokens, Some(error))
-                }
-            },
-            '(' => tokens.push_back(TokenData::new_onechar(Token::OpenParen, "(".to_string(), line, cursor, col)),
-            ')' => tokens.push_back(TokenData::new_onechar(Token::CloseParen, ")".to_string(), line, cursor, col)),
-            '|' => tokens.push_back(TokenData::new_onechar(Token::JoinType(JoinType::Or), "|".to_string(), line, cursor, col)),
-            '&' => tokens.push_back(TokenData::new_onechar(Token::JoinType(JoinType::And), "&".to_string(), line, cursor, col)),
-            '^' => tokens.push_back(TokenData::new_onechar(Token::JoinType(JoinType::Xor), "^".to_string(), line, cursor, col)),
-            '\n' => { line += 1; col = 0; cursor += 1; continue },
-            c if c.is_whitespace() => { },
-            c @ _ => return (tokens, Some(FilterError::new_onechar(format!("Unexpected character '{}'", c), line, cursor, col)))
-        }
+// Lexes a filter one token at a time, with tokens borrowing their source straight from the input. An error doesn't end
+// the iteration: lexing carries on after the characters that caused it, so iterating to the end finds every error. An
+// error's range is all the characters it consumed, and its span the ones at fault.
+pub struct Lexer<'a> {
+    input: &'a str,
+    position: Position
+}
 
-        col += 1;
-        cursor += 1;
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::starting_at(input, Position::default())
     }
 
-    (tokens, None)
-}
+    // Lexes `input` from `start`, which must be the position of a character boundary in `input`
+    pub fn starting_at(input: &'a str, start: Position) -> Self {
+        Self { input, position: start }
+    }
 
-pub fn lex_name(c: char, s: &mut Peekable<Chars>, cursor: &mut usize, line: usize, col: &mut usize) -> TokenData {
-    let start = *cursor;
-    let start_col = *col;
-    let mut name = String::from(c);
+    fn peek(&self) -> Option<char> {
+        self.input[self.position.byte..].chars().next()
+    }
 
-    while let Some(c) = s.peek() {
-        if !c.is_alphanumeric() && *c != '_' {
-            break;
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position = self.position.after(c);
+        Some(c)
+    }
+
+    fn bump_if(&mut self, predicate: impl Fn(char) -> bool) -> Option<char> {
+        match self.peek() {
+            Some(c) if predicate(c) => self.bump(),
+            _ => None
         }
+    }
 
-        name.push(*c);
-        s.next();
-        *col += 1;
-        *cursor += 1;
+    // The span of everything consumed since `start`
+    fn span(&self, start: Position) -> Span {
+        Span::new(start, self.position)
     }
 
-    TokenData::new_oneline(Token::Name(name.clone()), name, line, start, start_col, *cursor + 1, *col + 1)
+    fn since(&self, start: Position) -> &'a str {
+        self.span(start).slice(self.input)
+    }
+
+    // A token covering everything consumed since `start`
+    fn token(&self, token: Token<'a>, start: Position) -> TokenData<'a> {
+        TokenData::new(token, Cow::Borrowed(self.since(start)), self.span(start))
+    }
+
+    // An error covering everything consumed since `start`
+    fn error(&self, message: String, start: Position) -> FilterError {
+        FilterError::new(message, self.span(start))
+    }
 }
 
-pub fn lex_string(s: &mut Peekable<Chars>, cursor: &mut usize, line: &mut usize, col: &mut usize) -> TokenData {
-    let start = *cursor;
-    let start_line = *line;
-    let start_col = *col;
-    let mut value = String::new();
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<TokenData<'a>, FilterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.bump_if(char::is_whitespace).is_some() { }
+
+        Some(match self.peek()? {
+            '"' => Ok(lex_string(self)),
+            'a'..='z' | 'A'..='Z' | '_' => Ok(lex_name(self)),
+            '0'..='9' | '-' | '.' => lex_number(self),
+            '<' | '>' | '=' | '!' => lex_comparator(self),
+            '(' => Ok(lex_char(self, Token::OpenParen)),
+            ')' => Ok(lex_char(self, Token::CloseParen)),
+            '|' => Ok(lex_char(self, Token::JoinType(JoinType::Or))),
+            '&' => Ok(lex_char(self, Token::JoinType(JoinType::And))),
+            '^' => Ok(lex_char(self, Token::JoinType(JoinType::Xor))),
+            c => {
+                let start = self.position;
+                self.bump();
+                Err(self.error(format!("Unexpected character '{}'", c), start))
+            }
+        })
+    }
+}
 
-    while let Some(c) = s.next() {
-        *col += 1;
-        *cursor += 1;
+impl FusedIterator for Lexer<'_> { }
 
-        if c == '"' {
-            break;
-        }
-        else if c == '\n' {
-            *line += 1;
-            *col = 0;
-        }
+// Lexes all of `input`, stopping at the first error
+pub fn lex(input: &str) -> (Vec<TokenData<'_>>, Option<FilterError>) {
+    let mut tokens = Vec::new();
 
-        value.push(c);
+    for result in Lexer::new(input) {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(error) => return (tokens, Some(error))
+        }
     }
 
-    TokenData::new(Token::Value(Value::String(value.clone())), format!("\"{}\"", value), start, start_line, start_col, *cursor + 1, *line, *col + 1)
+    (tokens, None)
+}
+
+fn lex_char<'a>(s: &mut Lexer<'a>, token: Token<'a>) -> TokenData<'a> {
+    let start = s.position;
+    s.bump();
+    s.token(token, start)
+}
+
+fn lex_name<'a>(s: &mut Lexer<'a>) -> TokenData<'a> {
+    let start = s.position;
+    s.bump();
+    while s.bump_if(|c| c.is_alphanumeric() || c == '_').is_some() { }
+
+    s.token(Token::Name(Cow::Borrowed(s.since(start))), start)
 }
 
-pub fn lex_number(c: char, s: &mut Peekable<Chars>, cursor: &mut usize, line: usize, col: &mut usize) -> Result<TokenData, FilterError> {
-    let mut found_decimal = false;
-    let start = *cursor;
-    let start_col = *col;
-    let mut number_string = String::from(c);
-    let mut raw_string = String::from(c);
+fn lex_string<'a>(s: &mut Lexer<'a>) -> TokenData<'a> {
+    let start = s.position;
+    s.bump();
+
+    let value_start = s.position;
+    while s.bump_if(|c| c != '"').is_some() { }
+    let value = s.since(value_start);
+
+    // Consume the closing quote, unless the filter ended first
+    s.bump();
+
+    s.token(Token::Value(Value::String(Cow::Borrowed(value))), start)
+}
+
+fn lex_number<'a>(s: &mut Lexer<'a>) -> Result<TokenData<'a>, FilterError> {
+    let start = s.position;
+    let mut found_decimal = s.bump() == Some('.');
 
     while let Some(c) = s.peek() {
-        if !c.is_numeric() && *c != ',' && *c != '.' {
+        if !c.is_ascii_digit() && c != ',' && c != '.' {
             break;
         }
-        if *c == '.' {
+        if c == '.' {
             if found_decimal {
-                s.next();
-                *cursor += 1;
-                *col += 1;
-                return Err(FilterError::new_oneline_context("Unexpected second decimal place".to_string(), line, *cursor, *cursor + 1, start, start_col, *cursor + 1, *col + 1));
+                let decimal = s.position;
+                s.bump();
+                return Err(FilterError::new_with_range("Unexpected second decimal place".to_string(), s.span(start), s.span(decimal)));
             }
             else {
                 found_decimal = true;
             }
         }
 
-        // Allow commas for splitting large numbers, but not actually part of number
-        if *c != ',' {
-            number_string.push(*c);
-        }
-        raw_string.push(*c);
-        s.next();
-        *col += 1;
-        *cursor += 1;
+        s.bump();
     }
 
-    if "-" == number_string.as_str() {
-        return Err(FilterError::new_oneline("Expected a number following `-`".to_string(), line, start, start_col, *cursor + 1, *col + 1));
-    }
-    if "." == number_string.as_str() {
-        return Err(FilterError::new_oneline("Expected a number with `.`".to_string(), line, start, start_col, *cursor + 1, *col + 1));
+    let source = s.since(start);
+    // Allow commas for splitting large numbers, but not actually part of number
+    let number_string = if source.contains(',') { Cow::Owned(source.replace(',', "")) } else { Cow::Borrowed(source) };
+
+    match number_string.as_ref() {
+        "-" => return Err(s.error("Expected a number following `-`".to_string(), start)),
+        "." => return Err(s.error("Expected a number with `.`".to_string(), start)),
+        _ => {}
     }
 
-    Ok(
-        TokenData::new_oneline(Token::Value(Value::Number(number_string.parse::<f64>().unwrap())), raw_string, line, start, start_col, *cursor + 1, *col + 1) 
-    )
+    match number_string.parse::<f64>() {
+        Ok(number) => Ok(s.token(Token::Value(Value::Number(number)), start)),
+        Err(_) => Err(s.error(format!("Invalid number `{}`", source), start))
+    }
 }
 
-pub fn lex_comparator(c: char, s: &mut Peekable<Chars>, cursor: &mut usize, line: usize, col: &mut usize) -> Result<TokenData, FilterError> {
-    match c {
-        '>' => match s.peek() {
-            Some('=') => {
-                s.next();
-                *col += 1;
-                *cursor += 1;
-                Ok(
-                    TokenData::new_oneline(Token::Comparator(Comparator::GreaterThanOrEqual), ">=".to_string(), line, *cursor - 1, *col - 1, *cursor + 1, *col + 1)
-                )
-            },
-            _ => Ok(
-                TokenData::new_onechar(Token::Comparator(Comparator::GreaterThan), ">".to_string(), line, *cursor, *col)
-            )
+fn lex_comparator<'a>(s: &mut Lexer<'a>) -> Result<TokenData<'a>, FilterError> {
+    let start = s.position;
+
+    let comparator = match s.bump() {
+        Some('>') => if s.bump_if(|c| c == '=').is_some() { Comparator::GreaterThanOrEqual } else { Comparator::GreaterThan },
+        Some('<') => match s.peek() {
+            Some('=') => { s.bump(); Comparator::LessThanOrEqual },
+            Some('>') => return Err(near_miss_comparator(s, start, "!=")),
+            _ => Comparator::LessThan
         },
-        '<' => match s.peek() {
-            Some('=') => {
-                s.next();
-                *col += 1;
-                *cursor += 1;
-                Ok(
-                    TokenData::new_oneline(Token::Comparator(Comparator::LessThanOrEqual), "<=".to_string(), line, *cursor - 1, *col - 1, *cursor + 1, *col + 1)
-                )
-            },
-            _ => Ok(
-                TokenData::new_onechar(Token::Comparator(Comparator::LessThan), "<".to_string(), line, *cursor, *col)
-            )
+        Some('=') => match s.peek() {
+            Some('>') => return Err(near_miss_comparator(s, start, ">=")),
+            Some('<') => return Err(near_miss_comparator(s, start, "<=")),
+            Some('=') => return Err(near_miss_comparator(s, start, "=")),
+            _ => Comparator::Equal
         },
-        '=' => Ok(
-            TokenData::new_onechar(Token::Comparator(Comparator::Equal), "=".to_string(), line, *cursor, *col)
-        ),
-        '!' => match s.next() {
-            Some('=') => {
-                *col += 1;
-                *cursor += 1;
-                Ok(
-                    TokenData::new_oneline(Token::Comparator(Comparator::NotEqual), "!=".to_string(), line, *cursor - 1, *col - 1, *cursor + 1, *col + 1)
-                )
-            },
-            None => Err(FilterError::new_onechar("Unexpected end of filter after '!'".to_string(), line, *cursor, *col)),
-            Some(c) => {
-                *col += 1;
-                *cursor += 1;
-                Err(FilterError::new_oneline(format!("Unexpected character '{}' (expected `=` to make `!=`)", c), line, *cursor - 1, *col - 1, *cursor + 1, *col + 1))
-            }
+        Some('!') => match s.bump() {
+            Some('=') => Comparator::NotEqual,
+            None => return Err(s.error("Unexpected end of filter after '!'".to_string(), start)),
+            Some(c) => return Err(s.error(format!("Unexpected character '{}' (expected `=` to make `!=`)", c), start))
         },
-        _ => panic!("Passed invalid character `{}` to lex_comparator()", c)
-    }
+        c => panic!("Passed invalid character `{:?}` to lex_comparator()", c)
+    };
+
+    Ok(s.token(Token::Comparator(comparator), start))
+}
+
+// An error for a comparator from another language, e.g. `=>` or `==`, whose second character is next. Without this they'd
+// lex as two comparators in a row, which only fails later with a less helpful message.
+fn near_miss_comparator(s: &mut Lexer, start: Position, intended: &str) -> FilterError {
+    s.bump();
+    let error = s.error(format!("`{}` isn't a comparator; did you mean `{}`?", s.since(start), intended), start);
+    let fix = Fix::replace(error.span, intended);
+    error.with_fixes(vec![fix])
 }
 
 #[cfg(test)]
 mod lexer_tests {
     use super::*;
 
+    // A position in ASCII input, where bytes, chars and UTF-16 code units all line up
+    fn at(offset: usize, line: usize, col: usize) -> Position {
+        Position { byte: offset, char: offset, utf16: offset, line, col }
+    }
+
     #[test]
     pub fn lexes_equal_comparator() {
         let input = "=".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Comparator(Comparator::Equal),
-            source: "=".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "=".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -325,19 +319,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_not_equal_comparator() {
         let input = "!=".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Comparator(Comparator::NotEqual),
-            source: "!=".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 2,
-            end_line: 0,
-            end_col: 2
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "!=".into(),
+            span: Span::new(at(0, 0, 0), at(2, 0, 2))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -346,19 +334,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_less_than_comparator() {
         let input = "<".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Comparator(Comparator::LessThan),
-            source: "<".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "<".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -367,19 +349,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_less_than_or_equal_comparator() {
         let input = "<=".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Comparator(Comparator::LessThanOrEqual),
-            source: "<=".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 2,
-            end_line: 0,
-            end_col: 2
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "<=".into(),
+            span: Span::new(at(0, 0, 0), at(2, 0, 2))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -388,19 +364,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_greater_than_comparator() {
         let input = ">".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Comparator(Comparator::GreaterThan),
-            source: ">".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: ">".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -409,19 +379,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_greater_than_or_equal_comparator() {
         let input = ">=".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Comparator(Comparator::GreaterThanOrEqual),
-            source: ">=".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 2,
-            end_line: 0,
-            end_col: 2
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: ">=".into(),
+            span: Span::new(at(0, 0, 0), at(2, 0, 2))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -430,19 +394,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_and_join_type() {
         let input = "&".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::JoinType(JoinType::And),
-            source: "&".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "&".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -451,19 +409,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_or_join_type() {
         let input = "|".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::JoinType(JoinType::Or),
-            source: "|".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "|".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -472,19 +424,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_xor_join_type() {
         let input = "^".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::JoinType(JoinType::Xor),
-            source: "^".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "^".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -493,19 +439,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_name() {
         let input = "test".to_string();
-        let mut input = input.chars().peekable();
-
-        let expected = LinkedList::from([TokenData {
-            token: Token::Name("test".to_string()),
-            source: "test".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 4,
-            end_line: 0,
-            end_col: 4
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+
+        let expected = vec![TokenData {
+            token: Token::Name("test".into()),
+            source: "test".into(),
+            span: Span::new(at(0, 0, 0), at(4, 0, 4))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -514,19 +454,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_string() {
         let input = "\"test\"".to_string();
-        let mut input = input.chars().peekable();
-
-        let expected = LinkedList::from([TokenData {
-            token: Token::Value(Value::String("test".to_string())),
-            source: "\"test\"".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 6,
-            end_line: 0,
-            end_col: 6
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+
+        let expected = vec![TokenData {
+            token: Token::Value(Value::String("test".into())),
+            source: "\"test\"".into(),
+            span: Span::new(at(0, 0, 0), at(6, 0, 6))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -535,19 +469,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_positive_integer() {
         let input = "109".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Value(Value::Number(109.)),
-            source: "109".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 3,
-            end_line: 0,
-            end_col: 3
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "109".into(),
+            span: Span::new(at(0, 0, 0), at(3, 0, 3))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -556,19 +484,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_positive_real_number() {
         let input = "109.55".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Value(Value::Number(109.55)),
-            source: "109.55".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 6,
-            end_line: 0,
-            end_col: 6
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "109.55".into(),
+            span: Span::new(at(0, 0, 0), at(6, 0, 6))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -576,19 +498,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_positive_comma_separated_real_number() {
         let input = "62,109.55".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::Value(Value::Number(62_109.55)),
-            source: "62,109.55".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 9,
-            end_line: 0,
-            end_col: 9
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "62,109.55".into(),
+            span: Span::new(at(0, 0, 0), at(9, 0, 9))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -597,19 +513,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_open_parentheses() {
         let input = "(".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::OpenParen,
-            source: "(".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: "(".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -618,19 +528,13 @@ mod lexer_tests {
     #[test]
     pub fn lexes_close_parentheses() {
         let input = ")".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([TokenData {
+        let expected = vec![TokenData {
             token: Token::CloseParen,
-            source: ")".to_string(),
-            start: 0,
-            start_line: 0,
-            start_col: 0,
-            end: 1,
-            end_line: 0,
-            end_col: 1
-        }]);
-        let result = lex(&mut input, 0, 0, 0);
+            source: ")".into(),
+            span: Span::new(at(0, 0, 0), at(1, 0, 1))
+        }];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -639,41 +543,25 @@ mod lexer_tests {
     #[test]
     pub fn lexes_comparison() {
         let input = "test = \"test\"".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([
+        let expected = vec![
             TokenData {
-                token: Token::Name("test".to_string()),
-                source: "test".to_string(),
-                start: 0,
-                start_line: 0,
-                start_col: 0,
-                end: 4,
-                end_line: 0,
-                end_col: 4
+                token: Token::Name("test".into()),
+                source: "test".into(),
+                span: Span::new(at(0, 0, 0), at(4, 0, 4))
             },
             TokenData {
                 token: Token::Comparator(Comparator::Equal),
-                source: "=".to_string(),
-                start: 5,
-                start_line: 0,
-                start_col: 5,
-                end: 6,
-                end_line: 0,
-                end_col: 6
+                source: "=".into(),
+                span: Span::new(at(5, 0, 5), at(6, 0, 6))
             },
             TokenData {
-                token: Token::Value(Value::String("test".to_string())),
-                source: "\"test\"".to_string(),
-                start: 7,
-                start_line: 0,
-                start_col: 7,
-                end: 13,
-                end_line: 0,
-                end_col: 13
+                token: Token::Value(Value::String("test".into())),
+                source: "\"test\"".into(),
+                span: Span::new(at(7, 0, 7), at(13, 0, 13))
             },
-        ]);
-        let result = lex(&mut input, 0, 0, 0);
+        ];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -682,41 +570,25 @@ mod lexer_tests {
     #[test]
     pub fn lexes_comparison_without_spaces() {
         let input = "test=\"test\"".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([
+        let expected = vec![
             TokenData {
-                token: Token::Name("test".to_string()),
-                source: "test".to_string(),
-                start: 0,
-                start_line: 0,
-                start_col: 0,
-                end: 4,
-                end_line: 0,
-                end_col: 4
+                token: Token::Name("test".into()),
+                source: "test".into(),
+                span: Span::new(at(0, 0, 0), at(4, 0, 4))
             },
             TokenData {
                 token: Token::Comparator(Comparator::Equal),
-                source: "=".to_string(),
-                start: 4,
-                start_line: 0,
-                start_col: 4,
-                end: 5,
-                end_line: 0,
-                end_col: 5
+                source: "=".into(),
+                span: Span::new(at(4, 0, 4), at(5, 0, 5))
             },
             TokenData {
-                token: Token::Value(Value::String("test".to_string())),
-                source: "\"test\"".to_string(),
-                start: 5,
-                start_line: 0,
-                start_col: 5,
-                end: 11,
-                end_line: 0,
-                end_col: 11
+                token: Token::Value(Value::String("test".into())),
+                source: "\"test\"".into(),
+                span: Span::new(at(5, 0, 5), at(11, 0, 11))
             },
-        ]);
-        let result = lex(&mut input, 0, 0, 0);
+        ];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -725,41 +597,25 @@ mod lexer_tests {
     #[test]
     pub fn lexes_comparison_with_newline() {
         let input = "test =\n10".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([
+        let expected = vec![
             TokenData {
-                token: Token::Name("test".to_string()),
-                source: "test".to_string(),
-                start: 0,
-                start_line: 0,
-                start_col: 0,
-                end: 4,
-                end_line: 0,
-                end_col: 4
+                token: Token::Name("test".into()),
+                source: "test".into(),
+                span: Span::new(at(0, 0, 0), at(4, 0, 4))
             },
             TokenData {
                 token: Token::Comparator(Comparator::Equal),
-                source: "=".to_string(),
-                start: 5,
-                start_line: 0,
-                start_col: 5,
-                end: 6,
-                end_line: 0,
-                end_col: 6
+                source: "=".into(),
+                span: Span::new(at(5, 0, 5), at(6, 0, 6))
             },
             TokenData {
                 token: Token::Value(Value::Number(10.)),
-                source: "10".to_string(),
-                start: 7,
-                start_line: 1,
-                start_col: 0,
-                end: 9,
-                end_line: 1,
-                end_col: 2
+                source: "10".into(),
+                span: Span::new(at(7, 1, 0), at(9, 1, 2))
             },
-        ]);
-        let result = lex(&mut input, 0, 0, 0);
+        ];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -768,81 +624,45 @@ mod lexer_tests {
     #[test]
     pub fn lexes_joined_comparisons() {
         let input = "test = 10,000 | test_2  !=\"test_2\"".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([
+        let expected = vec![
             TokenData {
-                token: Token::Name("test".to_string()),
-                source: "test".to_string(),
-                start: 0,
-                start_line: 0,
-                start_col: 0,
-                end: 4,
-                end_line: 0,
-                end_col: 4
+                token: Token::Name("test".into()),
+                source: "test".into(),
+                span: Span::new(at(0, 0, 0), at(4, 0, 4))
             },
             TokenData {
                 token: Token::Comparator(Comparator::Equal),
-                source: "=".to_string(),
-                start: 5,
-                start_line: 0,
-                start_col: 5,
-                end: 6,
-                end_line: 0,
-                end_col: 6
+                source: "=".into(),
+                span: Span::new(at(5, 0, 5), at(6, 0, 6))
             },
             TokenData {
                 token: Token::Value(Value::Number(10_000.)),
-                source: "10,000".to_string(),
-                start: 7,
-                start_line: 0,
-                start_col: 7,
-                end: 13,
-                end_line: 0,
-                end_col: 13
+                source: "10,000".into(),
+                span: Span::new(at(7, 0, 7), at(13, 0, 13))
             },
             TokenData {
                 token: Token::JoinType(JoinType::Or),
-                source: "|".to_string(),
-                start: 14,
-                start_line: 0,
-                start_col: 14,
-                end: 15,
-                end_line: 0,
-                end_col: 15
+                source: "|".into(),
+                span: Span::new(at(14, 0, 14), at(15, 0, 15))
             },
             TokenData {
-                token: Token::Name("test_2".to_string()),
-                source: "test_2".to_string(),
-                start: 16,
-                start_line: 0,
-                start_col: 16,
-                end: 22,
-                end_line: 0,
-                end_col: 22
+                token: Token::Name("test_2".into()),
+                source: "test_2".into(),
+                span: Span::new(at(16, 0, 16), at(22, 0, 22))
             },
             TokenData {
                 token: Token::Comparator(Comparator::NotEqual),
-                source: "!=".to_string(),
-                start: 24,
-                start_line: 0,
-                start_col: 24,
-                end: 26,
-                end_line: 0,
-                end_col: 26
+                source: "!=".into(),
+                span: Span::new(at(24, 0, 24), at(26, 0, 26))
             },
             TokenData {
-                token: Token::Value(Value::String("test_2".to_string())),
-                source: "\"test_2\"".to_string(),
-                start: 26,
-                start_line: 0,
-                start_col: 26,
-                end: 34,
-                end_line: 0,
-                end_col: 34
+                token: Token::Value(Value::String("test_2".into())),
+                source: "\"test_2\"".into(),
+                span: Span::new(at(26, 0, 26), at(34, 0, 34))
             },
-        ]);
-        let result = lex(&mut input, 0, 0, 0);
+        ];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -851,81 +671,45 @@ mod lexer_tests {
     #[test]
     pub fn lexes_joined_comparisons_with_newline() {
         let input = "test = \"test\"\n| test_2  !=\"test_2\"".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([
+        let expected = vec![
             TokenData {
-                token: Token::Name("test".to_string()),
-                source: "test".to_string(),
-                start: 0,
-                start_line: 0,
-                start_col: 0,
-                end: 4,
-                end_line: 0,
-                end_col: 4
+                token: Token::Name("test".into()),
+                source: "test".into(),
+                span: Span::new(at(0, 0, 0), at(4, 0, 4))
             },
             TokenData {
                 token: Token::Comparator(Comparator::Equal),
-                source: "=".to_string(),
-                start: 5,
-                start_line: 0,
-                start_col: 5,
-                end: 6,
-                end_line: 0,
-                end_col: 6
+                source: "=".into(),
+                span: Span::new(at(5, 0, 5), at(6, 0, 6))
             },
             TokenData {
-                token: Token::Value(Value::String("test".to_string())),
-                source: "\"test\"".to_string(),
-                start: 7,
-                start_line: 0,
-                start_col: 7,
-                end: 13,
-                end_line: 0,
-                end_col: 13
+                token: Token::Value(Value::String("test".into())),
+                source: "\"test\"".into(),
+                span: Span::new(at(7, 0, 7), at(13, 0, 13))
             },
             TokenData {
                 token: Token::JoinType(JoinType::Or),
-                source: "|".to_string(),
-                start: 14,
-                start_line: 1,
-                start_col: 0,
-                end: 15,
-                end_line: 1,
-                end_col: 1
+                source: "|".into(),
+                span: Span::new(at(14, 1, 0), at(15, 1, 1))
             },
             TokenData {
-                token: Token::Name("test_2".to_string()),
-                source: "test_2".to_string(),
-                start: 16,
-                start_line: 1,
-                start_col: 2,
-                end: 22,
-                end_line: 1,
-                end_col: 8
+                token: Token::Name("test_2".into()),
+                source: "test_2".into(),
+                span: Span::new(at(16, 1, 2), at(22, 1, 8))
             },
             TokenData {
                 token: Token::Comparator(Comparator::NotEqual),
-                source: "!=".to_string(),
-                start: 24,
-                start_line: 1,
-                start_col: 10,
-                end: 26,
-                end_line: 1,
-                end_col: 12
+                source: "!=".into(),
+                span: Span::new(at(24, 1, 10), at(26, 1, 12))
             },
             TokenData {
-                token: Token::Value(Value::String("test_2".to_string())),
-                source: "\"test_2\"".to_string(),
-                start: 26,
-                start_line: 1,
-                start_col: 12,
-                end: 34,
-                end_line: 1,
-                end_col: 20
+                token: Token::Value(Value::String("test_2".into())),
+                source: "\"test_2\"".into(),
+                span: Span::new(at(26, 1, 12), at(34, 1, 20))
             },
-        ]);
-        let result = lex(&mut input, 0, 0, 0);
+        ];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_eq!(result.1, None);
@@ -934,24 +718,22 @@ mod lexer_tests {
     #[test]
     pub fn errors_on_unexpected_character() {
         let input = "@".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
         let result = result.1.unwrap();
-        assert_eq!(result.start, 0);
-        assert_eq!(result.start_line, 0);
-        assert_eq!(result.start, 0);
-        assert_eq!(result.end, 1);
+        assert_eq!(result.span.start.byte, 0);
+        assert_eq!(result.span.start.line, 0);
+        assert_eq!(result.span.start.byte, 0);
+        assert_eq!(result.span.end.byte, 1);
     }
 
     #[test]
     pub fn errors_on_number_with_extra_decimal() {
         let input = "100.00.0".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
     }
@@ -959,9 +741,8 @@ mod lexer_tests {
     #[test]
     pub fn errors_on_negative_without_number() {
         let input = "- |".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
     }
@@ -969,9 +750,8 @@ mod lexer_tests {
     #[test]
     pub fn errors_on_decimal_without_number() {
         let input = ". |".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
     }
@@ -979,9 +759,8 @@ mod lexer_tests {
     #[test]
     pub fn errors_on_incomplete_not_equal() {
         let input = "test ! \"test\"".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
     }
@@ -989,9 +768,8 @@ mod lexer_tests {
     #[test]
     pub fn errors_on_incomplete_not_equal_2() {
         let input = "test !".to_string();
-        let mut input = input.chars().peekable();
         
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
     }
@@ -999,148 +777,308 @@ mod lexer_tests {
     #[test]
     pub fn unexpected_character_error_includes_right_metadata() {
         let input = "test = 2.3 |\n test_2 @ 5".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
         let result = result.1.unwrap();
-        assert_eq!(result.start, 21);
-        assert_eq!(result.start_line, 1);
-        assert_eq!(result.start_col, 8);
-        assert_eq!(result.end, 22);
-        assert_eq!(result.end_line, 1);
-        assert_eq!(result.end_col, 9);
+        assert_eq!(result.span.start.byte, 21);
+        assert_eq!(result.span.start.line, 1);
+        assert_eq!(result.span.start.col, 8);
+        assert_eq!(result.span.end.byte, 22);
+        assert_eq!(result.span.end.line, 1);
+        assert_eq!(result.span.end.col, 9);
     }
 
     #[test]
     pub fn number_with_extra_decimal_error_includes_right_metadata() {
         let input = "test = 2.3 |\n test_2 > 100.00.0".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
         let result = result.1.unwrap();
-        assert_eq!(result.start, 23);
-        assert_eq!(result.start_line, 1);
-        assert_eq!(result.start_col, 10);
-        assert_eq!(result.end, 30);
-        assert_eq!(result.end_line, 1);
-        assert_eq!(result.end_col, 17);
+        assert_eq!(result.span.start.byte, 29);
+        assert_eq!(result.span.start.line, 1);
+        assert_eq!(result.span.start.col, 16);
+        assert_eq!(result.span.end.byte, 30);
+        assert_eq!(result.span.end.line, 1);
+        assert_eq!(result.span.end.col, 17);
+        assert_eq!(result.range.start.byte, 23);
+        assert_eq!(result.range.start.col, 10);
+        assert_eq!(result.range.end.byte, 30);
     }
 
     #[test]
     pub fn decimal_without_number_error_includes_right_metadata() {
         let input = "test = 2.3 |\n test_2 > . |".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
         let result = result.1.unwrap();
-        assert_eq!(result.start, 23);
-        assert_eq!(result.start_line, 1);
-        assert_eq!(result.start_col, 10);
-        assert_eq!(result.end, 24);
-        assert_eq!(result.end_line, 1);
-        assert_eq!(result.end_col, 11);
+        assert_eq!(result.span.start.byte, 23);
+        assert_eq!(result.span.start.line, 1);
+        assert_eq!(result.span.start.col, 10);
+        assert_eq!(result.span.end.byte, 24);
+        assert_eq!(result.span.end.line, 1);
+        assert_eq!(result.span.end.col, 11);
     }
 
     #[test]
     pub fn incomplete_not_equal_error_includes_right_metadata() {
         let input = "test = 2.3 |\n test_2 ! \"test\"".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
         let result = result.1.unwrap();
-        assert_eq!(result.start, 21);
-        assert_eq!(result.start_line, 1);
-        assert_eq!(result.start_col, 8);
-        assert_eq!(result.end, 23);
-        assert_eq!(result.end_line, 1);
-        assert_eq!(result.end_col, 10);
+        assert_eq!(result.span.start.byte, 21);
+        assert_eq!(result.span.start.line, 1);
+        assert_eq!(result.span.start.col, 8);
+        assert_eq!(result.span.end.byte, 23);
+        assert_eq!(result.span.end.line, 1);
+        assert_eq!(result.span.end.col, 10);
     }
 
     #[test]
     pub fn incomplete_not_equal_error_includes_right_metadata2() {
         let input = "test = 2.3 |\n test_2 !".to_string();
-        let mut input = input.chars().peekable();
 
-        let result = lex(&mut input, 0, 0, 0);
+        let result = lex(&input);
 
         assert_ne!(result.1, None);
         let result = result.1.unwrap();
-        assert_eq!(result.start, 21);
-        assert_eq!(result.start_line, 1);
-        assert_eq!(result.start_col, 8);
-        assert_eq!(result.end, 22);
-        assert_eq!(result.end_line, 1);
-        assert_eq!(result.end_col, 9);
+        assert_eq!(result.span.start.byte, 21);
+        assert_eq!(result.span.start.line, 1);
+        assert_eq!(result.span.start.col, 8);
+        assert_eq!(result.span.end.byte, 22);
+        assert_eq!(result.span.end.line, 1);
+        assert_eq!(result.span.end.col, 9);
     }
 
     #[test]
     pub fn errors_include_prior_lex_data() {
         let input = "test = 2 | test_2 !".to_string();
-        let mut input = input.chars().peekable();
 
-        let expected = LinkedList::from([
+        let expected = vec![
             TokenData {
-                token: Token::Name("test".to_string()),
-                source: "test".to_string(),
-                start: 0,
-                start_line: 0,
-                start_col: 0,
-                end: 4,
-                end_line: 0,
-                end_col: 4
+                token: Token::Name("test".into()),
+                source: "test".into(),
+                span: Span::new(at(0, 0, 0), at(4, 0, 4))
             },
             TokenData {
                 token: Token::Comparator(Comparator::Equal),
-                source: "=".to_string(),
-                start: 5,
-                start_line: 0,
-                start_col: 5,
-                end: 6,
-                end_line: 0,
-                end_col: 6
+                source: "=".into(),
+                span: Span::new(at(5, 0, 5), at(6, 0, 6))
             },
             TokenData {
                 token: Token::Value(Value::Number(2.)),
-                source: "2".to_string(),
-                start: 7,
-                start_line: 0,
-                start_col: 7,
-                end: 8,
-                end_line: 0,
-                end_col: 8
+                source: "2".into(),
+                span: Span::new(at(7, 0, 7), at(8, 0, 8))
             },
             TokenData {
                 token: Token::JoinType(JoinType::Or),
-                source: "|".to_string(),
-                start: 9,
-                start_line: 0,
-                start_col: 9,
-                end: 10,
-                end_line: 0,
-                end_col: 10
+                source: "|".into(),
+                span: Span::new(at(9, 0, 9), at(10, 0, 10))
             },
             TokenData {
-                token: Token::Name("test_2".to_string()),
-                source: "test_2".to_string(),
-                start: 11,
-                start_line: 0,
-                start_col: 11,
-                end: 17,
-                end_line: 0,
-                end_col: 17
+                token: Token::Name("test_2".into()),
+                source: "test_2".into(),
+                span: Span::new(at(11, 0, 11), at(17, 0, 17))
             }
-        ]);
-        let result = lex(&mut input, 0, 0, 0);
+        ];
+        let result = lex(&input);
 
         assert_eq!(result.0, expected);
         assert_ne!(result.1, None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn borrows_from_input() {
+        let input = "test = \"test\"".to_string();
+
+        let (tokens, error) = lex(&input);
+
+        assert!(matches!(tokens[0].token, Token::Name(Cow::Borrowed("test"))));
+        assert!(matches!(tokens[2].token, Token::Value(Value::String(Cow::Borrowed("test")))));
+        assert!(tokens.iter().all(|token| matches!(token.source, Cow::Borrowed(_))));
+        assert_eq!(error, None);
+    }
+
+    #[test]
+    pub fn counts_offsets_in_bytes_chars_and_utf16() {
+        // `ï` is 2 bytes, `😀` is 4 bytes and a UTF-16 surrogate pair, and `日` is 3 bytes
+        let input = "naïve = \"😀日\"".to_string();
+
+        let expected = vec![
+            TokenData::new(
+                Token::Name("naïve".into()), "naïve".into(),
+                Span::new(
+                    Position { byte: 0, char: 0, utf16: 0, line: 0, col: 0 },
+                    Position { byte: 6, char: 5, utf16: 5, line: 0, col: 5 }
+                )
+            ),
+            TokenData::new(
+                Token::Comparator(Comparator::Equal), "=".into(),
+                Span::new(
+                    Position { byte: 7, char: 6, utf16: 6, line: 0, col: 6 },
+                    Position { byte: 8, char: 7, utf16: 7, line: 0, col: 7 }
+                )
+            ),
+            TokenData::new(
+                Token::Value(Value::String("😀日".into())), "\"😀日\"".into(),
+                Span::new(
+                    Position { byte: 9, char: 8, utf16: 8, line: 0, col: 8 },
+                    Position { byte: 18, char: 12, utf16: 13, line: 0, col: 12 }
+                )
+            )
+        ];
+        let result = lex(&input);
+
+        assert_eq!(result.0, expected);
+        assert_eq!(result.1, None);
+    }
+
+    #[test]
+    pub fn offsets_slice_source_in_every_unit() {
+        let input = "naïve = \"😀\" |\n\t𝔁 > -1,000.5 & (日本 != \"\u{FEFF}\")".to_string();
+        let chars: Vec<char> = input.chars().collect();
+        let utf16: Vec<u16> = input.encode_utf16().collect();
+
+        for result in Lexer::new(&input) {
+            let (start, end, source) = match &result {
+                Ok(token) => (token.span.start, token.span.end, token.source.to_string()),
+                Err(error) => (error.range.start, error.range.end, error.range.slice(&input).to_string())
+            };
+
+            assert_eq!(input[start.byte..end.byte], source);
+            assert_eq!(chars[start.char..end.char].iter().collect::<String>(), source);
+            assert_eq!(utf16[start.utf16..end.utf16], source.encode_utf16().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    pub fn spans_slice_source_for_every_token_kind() {
+        let inputs = [
+            "a = 1", "a != -2.5", "a < .5", "a > 1,000", "a <= \"\"", "a >= \"x y\"",
+            "(a = 1 | b = 2) & c = 3 ^ d = 4",
+            "multi\nline = \"multi\nline\"\n& x = 1",
+            "unterminated = \"string",
+            "trailing_name"
+        ];
+
+        for input in inputs {
+            let (tokens, error) = lex(input);
+            assert_eq!(error, None, "{}", input);
+
+            let mut last_end = Position::default();
+            for token in tokens {
+                assert_eq!(token.span.slice(input), token.source, "{}", input);
+                assert!(!token.span.is_empty(), "{}", input);
+
+                // Tokens are in order, and only whitespace separates them
+                assert!(input[last_end.byte..token.span.start.byte].chars().all(char::is_whitespace), "{}", input);
+                last_end = token.span.end;
+            }
+            assert!(input[last_end.byte..].chars().all(char::is_whitespace), "{}", input);
+        }
+    }
+
+    #[test]
+    pub fn error_spans_cover_the_offending_text() {
+        let cases = [("a @ 1", "@"), ("a !x 1", "!x"), ("a = -", "-"), ("a = .", "."), ("a = 1.2.3", "."), ("a = 1 ²", "²")];
+
+        for (input, expected) in cases {
+            let error = lex(input).1.unwrap();
+
+            assert_eq!(error.span.slice(input), expected, "{}", input);
+            assert!(error.range.start.byte <= error.span.start.byte && error.span.end.byte <= error.range.end.byte, "{}", input);
+        }
+
+        // A second decimal place points at the `.` itself, in the context of the whole number
+        let error = lex("a = 1.2.3").1.unwrap();
+        assert_eq!(error.range.slice("a = 1.2.3"), "1.2.");
+    }
+
+    #[test]
+    pub fn errors_on_surrogate_pair_characters() {
+        let error = lex("test = 1 😀").1.unwrap();
+
+        assert_eq!(error.message, "Unexpected character '😀'");
+        assert_eq!(error.span.start, Position { byte: 9, char: 9, utf16: 9, line: 0, col: 9 });
+        assert_eq!(error.span.end, Position { byte: 13, char: 10, utf16: 11, line: 0, col: 10 });
+    }
+
+    #[test]
+    pub fn tracks_columns_after_multiline_string() {
+        let input = "test = \"a\nb\" | test_2".to_string();
+
+        let (tokens, error) = lex(&input);
+
+        assert_eq!((tokens[2].span.end.line, tokens[2].span.end.col), (1, 2));
+        assert_eq!((tokens[3].span.start.line, tokens[3].span.start.col), (1, 3));
+        assert_eq!((tokens[4].span.start.line, tokens[4].span.start.col), (1, 5));
+        assert_eq!(error, None);
+    }
+
+    #[test]
+    pub fn errors_on_malformed_numbers() {
+        let error = lex("test = -.").1.unwrap();
+        assert_eq!(error.message, "Invalid number `-.`");
+        assert_eq!((error.span.start.byte, error.span.end.byte), (7, 9));
+
+        let error = lex("test = .5.5").1.unwrap();
+        assert_eq!(error.message, "Unexpected second decimal place");
+
+        let error = lex("test = 1²").1.unwrap();
+        assert_eq!(error.message, "Unexpected character '²'");
+        assert_eq!((error.span.start.byte, error.span.end.byte, error.span.start.col, error.span.end.col), (8, 10, 8, 9));
+    }
+
+    #[test]
+    pub fn suggests_fixes_for_comparators_from_other_languages() {
+        for (input, intended) in [("a => 1", ">="), ("a =< 1", "<="), ("a == 1", "="), ("a <> 1", "!=")] {
+            let error = lex(input).1.unwrap();
+
+            assert_eq!(error.message, format!("`{}` isn't a comparator; did you mean `{}`?", &input[2..4], intended));
+            assert_eq!(error.span.slice(input), &input[2..4]);
+            assert_eq!(error.fixes, [Fix::replace(error.span, intended)]);
+        }
+
+        assert_eq!(lex("a >= 1 & b <= 2 & c = 3").1, None);
+    }
+
+    #[test]
+    pub fn iterator_recovers_from_errors() {
+        let input = "test @ test_2 !x = 1".to_string();
+
+        let result: Vec<_> = Lexer::new(&input).collect();
+
+        assert_eq!(result.len(), 6);
+        assert_eq!(result[0].as_ref().unwrap().source, "test");
+        assert_eq!(result[1].as_ref().unwrap_err().message, "Unexpected character '@'");
+        assert_eq!(result[2].as_ref().unwrap().source, "test_2");
+        assert_eq!(result[3].as_ref().unwrap_err().message, "Unexpected character 'x' (expected `=` to make `!=`)");
+        assert_eq!(result[4].as_ref().unwrap().token, Token::Comparator(Comparator::Equal));
+        assert_eq!(result[5].as_ref().unwrap().token, Token::Value(Value::Number(1.)));
+    }
+
+    #[test]
+    pub fn iterator_reports_every_error() {
+        let input = "@".repeat(100_000);
+
+        let errors = Lexer::new(&input).filter(Result::is_err).count();
+
+        assert_eq!(errors, 100_000);
+    }
+
+    #[test]
+    pub fn iterator_starts_at_offset() {
+        let input = "test = 1 |\n test_2".to_string();
+
+        let token = Lexer::starting_at(&input, at(10, 0, 10)).next().unwrap().unwrap();
+
+        assert_eq!(token, TokenData::new(Token::Name("test_2".into()), "test_2".into(), Span::new(at(12, 1, 1), at(18, 1, 7))));
+    }
+}
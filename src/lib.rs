@@ -1,16 +1,30 @@
 mod utils;
+pub mod completion;
+pub mod diagnostics;
+pub mod elasticsearch;
+pub mod eval;
+pub mod explain;
+pub mod hover;
+pub mod import;
+pub mod incremental;
+pub mod javascript;
 pub mod lexer;
+pub mod mongo;
 pub mod parser;
+pub mod printer;
+pub mod schema;
+pub mod sql;
+pub mod suggest;
+pub mod translate;
 pub mod types;
 
-use std::iter::Peekable;
-use std::str::Chars;
 use wasm_bindgen::prelude::*;
 use crate::lexer::{BareToken, BareTokenData};
 use crate::types::*;
 
-// When the `wee_alloc` feature is enabled, use `wee_alloc` as the global allocator.
-#[cfg(feature = "wee_alloc")]
+// When the `wee_alloc` feature is enabled, use `wee_alloc` as the global allocator. It's only meant for Wasm, so the
+// native binaries keep the system allocator.
+#[cfg(all(feature = "wee_alloc", target_arch = "wasm32"))]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
@@ -34,55 +48,86 @@ pub struct LexData {
 pub fn lex_filter(filter: &str) -> LexData {
     utils::set_panic_hook();
 
-    let filter = String::from(filter);
-    let mut filter = filter.chars().peekable();
+    let mut data = LexData { tokens: Vec::new(), errors: Vec::new() };
+
+    for result in lexer::Lexer::new(filter) {
+        match result {
+            Ok(token) => data.tokens.push(token.to_bare()),
+            Err(error) => {
+                data.tokens.push(BareTokenData { token: BareToken::Error, span: error.range });
+                data.errors.push(error);
+            }
+        }
+    }
 
-    run_lex(&mut filter, 0, 0, 0)
+    data
 }
 
-fn run_lex(mut filter: &mut Peekable<Chars>, cursor: usize, line: usize, col: usize) -> LexData {
-    let mut data = LexData { tokens: Vec::new(), errors: Vec::new() };
+// Type checks `filter` against `schema`. Filters that don't lex or parse have no errors here, since `lex_filter` and
+// `parse_filter` report those.
+#[wasm_bindgen]
+pub fn check_filter(filter: &str, schema: &schema::Schema) -> Vec<FilterError> {
+    utils::set_panic_hook();
 
-    let result = lexer::lex(&mut filter, cursor, line, col);
+    let lexed_filter = lexer::lex(filter);
+    if lexed_filter.1.is_some() {
+        return Vec::new();
+    }
 
-    match result.1 {
-        None => {
-            for token in result.0 {
-                data.tokens.push(token.to_bare());
-            };
-        }
+    match parser::parse(lexed_filter.0) {
+        Ok(search) => schema::check(&search, schema),
+        Err(_) => Vec::new()
+    }
+}
 
-        Some(error) => {
-            for token in result.0 {
-                data.tokens.push(token.to_bare());
-            };
-            data.tokens.push(BareTokenData {
-                token: BareToken::Error,
-                start: error.start,
-                start_line: error.start_line,
-                start_col: error.start_col,
-                end: error.end,
-                end_line: error.end_line,
-                end_col: error.end_col
-            });
-
-            // Restart lexing at the next character
-            let mut result = run_lex(&mut filter, error.end, error.end_line, error.end_col);
- 
-            data.errors.push(error);
-            data.tokens.append(&mut result.tokens);
-            data.errors.append(&mut result.errors);
-        }
+// Every error in `filter`: its syntax errors (see `try_parse`) if it has any, otherwise its type errors
+#[wasm_bindgen]
+pub fn filter_errors(filter: &str, schema: &schema::Schema) -> Vec<FilterError> {
+    utils::set_panic_hook();
+
+    match try_parse(filter) {
+        Ok(search) => schema::check(&search, schema),
+        Err(errors) => errors
     }
+}
 
-    data
+// Parses `filter`, or gives all of its lexing errors if it has any, otherwise its parse error. Parse errors other than
+// misplaced parentheses don't know where they happened, so they cover the whole filter.
+pub fn try_parse(filter: &str) -> Result<parser::Search<'_>, Vec<FilterError>> {
+    let errors: Vec<_> = lexer::Lexer::new(filter).filter_map(Result::err).collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let tokens = lexer::lex(filter).0;
+    parser::check_parens(&tokens).map_err(|error| vec![error])?;
+    parser::parse(tokens).map_err(|message| {
+        let end = filter.chars().fold(Position::default(), Position::after);
+        vec![FilterError::new(message, Span::new(Position::default(), end))]
+    })
+}
+
+// Suggestions for what could be typed at `cursor_offset` (in UTF-16 code units) in `text`, best first
+#[wasm_bindgen]
+pub fn complete(text: &str, cursor_offset: usize, schema: &schema::Schema) -> Vec<completion::Completion> {
+    utils::set_panic_hook();
+
+    completion::complete(text, cursor_offset, schema)
+}
+
+// What to show about the token at `cursor_offset` (in UTF-16 code units) in `text`, if anything
+#[wasm_bindgen]
+pub fn hover(text: &str, cursor_offset: usize, schema: &schema::Schema) -> Option<hover::Hover> {
+    utils::set_panic_hook();
+
+    hover::hover(text, cursor_offset, schema)
 }
 
 #[wasm_bindgen]
 pub fn parse_filter(filter: &str) {
     utils::set_panic_hook();
 
-    let lexed_filter = lexer::lex(&mut String::from(filter).chars().peekable(), 0, 0, 0);
+    let lexed_filter = lexer::lex(filter);
     alert(format!("{:?}", lexed_filter).to_string().as_str());
 
     if lexed_filter.1.is_some() {
@@ -91,4 +136,62 @@ pub fn parse_filter(filter: &str) {
     
     let parsed_filter = parser::parse(lexed_filter.0);
     alert(format!("{:?}", parsed_filter).to_string().as_str());
+}
+
+#[wasm_bindgen]
+pub fn format_filter(filter: &str) -> Result<String, String> {
+    utils::set_panic_hook();
+
+    let lexed_filter = lexer::lex(filter);
+    if let Some(error) = lexed_filter.1 {
+        return Err(error.message);
+    };
+
+    let parsed_filter = parser::parse(lexed_filter.0)?;
+    Ok(parsed_filter.to_string())
+}
+
+// Why `record`, a JSON object, does or doesn't match `filter`, as JSON: see `Explanation::to_json`
+#[wasm_bindgen]
+pub fn explain_filter(filter: &str, record: &str) -> Result<String, String> {
+    utils::set_panic_hook();
+    let lexed_filter = lexer::lex(filter);
+    if let Some(error) = lexed_filter.1 {
+        return Err(error.message);
+    };
+    let parsed_filter = parser::parse(lexed_filter.0)?;
+
+    let record = match serde_json::from_str(record) {
+        Ok(serde_json::Value::Object(record)) => record,
+        Ok(_) => return Err("The record isn't a JSON object".to_string()),
+        Err(error) => return Err(format!("The record isn't valid JSON: {}", error))
+    };
+    Ok(explain::explain(&parsed_filter, &record).to_json().to_string())
+}
+
+#[wasm_bindgen]
+pub fn import_lucene(query: &str) -> Result<String, String> {
+    utils::set_panic_hook();
+
+    Ok(import::from_lucene(query)?.to_string())
+}
+
+#[wasm_bindgen]
+pub fn import_query_string(query: &str) -> Result<String, String> {
+    utils::set_panic_hook();
+
+    Ok(import::from_query_string(query)?.to_string())
+}
+
+#[wasm_bindgen]
+pub fn filter_to_javascript(filter: &str) -> Result<String, String> {
+    utils::set_panic_hook();
+
+    let lexed_filter = lexer::lex(filter);
+    if let Some(error) = lexed_filter.1 {
+        return Err(error.message);
+    };
+
+    let parsed_filter = parser::parse(lexed_filter.0)?;
+    Ok(javascript::to_javascript(&parsed_filter))
 }
\ No newline at end of file
@@ -0,0 +1,63 @@
+use serde_json::{json, Value as Json};
+use crate::lexer::{Comparator, JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+use crate::translate::check_xor_expansion;
+
+// Translates `search` into a MongoDB query filter document, e.g. `{"$and": [{"age": {"$gt": 30.0}}, ...]}`.
+//
+// MongoDB has no `$xor`, so `a ^ b` is rewritten as `(a & !b) | (!a & b)`:
+//
+//     {"$or": [{"$and": [a, {"$nor": [b]}]}, {"$and": [{"$nor": [a]}, b]}]}
+//
+// Longer chains are folded from the left (`a ^ b ^ c` is `(a ^ b) ^ c`), and ones too long to write out this way are an
+// error (see `translate`). Note that `$ne`, like `$nor`, follows MongoDB's own rules and also matches documents without
+// the field.
+pub fn to_mongo(search: &Search) -> Result<Json, String> {
+    check_xor_expansion(search)?;
+    Ok(search_to_mongo(search))
+}
+
+fn search_to_mongo(search: &Search) -> Json {
+    match search.join_type {
+        _ if search.comparisons.len() == 1 => operand_to_mongo(&search.comparisons[0]),
+
+        JoinType::And if search.comparisons.is_empty() => json!({}),
+        JoinType::And => json!({ "$and": search.comparisons.iter().map(operand_to_mongo).collect::<Vec<_>>() }),
+
+        // An empty `Or` or `Xor` has no operand that could hold, so match nothing
+        JoinType::Or | JoinType::Xor if search.comparisons.is_empty() => json!({ "$nor": [{}] }),
+        JoinType::Or => json!({ "$or": search.comparisons.iter().map(operand_to_mongo).collect::<Vec<_>>() }),
+
+        JoinType::Xor => search.comparisons.iter()
+            .map(operand_to_mongo)
+            .reduce(|left, right| json!({ "$or": [
+                { "$and": [left.clone(), { "$nor": [right.clone()] }] },
+                { "$and": [{ "$nor": [left] }, right] }
+            ]}))
+            .unwrap()
+    }
+}
+
+fn operand_to_mongo(operand: &ComparisonOrSearch) -> Json {
+    match operand {
+        ComparisonOrSearch::Comparison(comparison) => comparison_to_mongo(comparison),
+        ComparisonOrSearch::Search(search) => search_to_mongo(search)
+    }
+}
+
+fn comparison_to_mongo(comparison: &Comparison) -> Json {
+    let operator = match comparison.comparator {
+        Comparator::Equal => "$eq",
+        Comparator::NotEqual => "$ne",
+        Comparator::LessThan => "$lt",
+        Comparator::GreaterThan => "$gt",
+        Comparator::LessThanOrEqual => "$lte",
+        Comparator::GreaterThanOrEqual => "$gte"
+    };
+    let value = match &comparison.value {
+        Value::Number(number) => json!(number),
+        Value::String(string) => json!(string)
+    };
+
+    json!({ comparison.name.as_ref(): { operator: value } })
+}
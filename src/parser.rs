@@ -1,159 +1,378 @@
-use std::collections::LinkedList;
+use std::borrow::Cow;
 use crate::lexer::{Comparator, JoinType, Token, TokenData, Value};
-
-#[derive(Debug, PartialEq)]
-pub struct Comparison {
-    pub name: String,
+use crate::types::{FilterError, Span};
+
+// Nodes keep the spans of the text they were parsed from, so tooling can point back into the filter, and their `NodeId`.
+// Neither is part of a node's meaning: they're ignored when comparing nodes and aren't serialized, and nodes built some
+// other way (e.g. deserialized) have empty spans and no IDs until `Search::number_nodes` is called.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comparison<'a> {
+    pub name: Cow<'a, str>,
     pub comparator: Comparator,
-    pub value: Value
+    pub value: Value<'a>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub id: NodeId,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub span: Span,             // From the start of the name to the end of the value
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub name_span: Span,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub comparator_span: Span,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub value_span: Span
+}
+
+impl<'a> Comparison<'a> {
+    pub fn new(name: Cow<'a, str>, comparator: Comparator, value: Value<'a>) -> Self {
+        Self {
+            name,
+            comparator,
+            value,
+            id: NodeId::default(),
+            span: Span::default(),
+            name_span: Span::default(),
+            comparator_span: Span::default(),
+            value_span: Span::default()
+        }
+    }
+}
+
+impl PartialEq for Comparison<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.comparator == other.comparator && self.value == other.value
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Search<'a> {
+    pub comparisons: Vec<ComparisonOrSearch<'a>>,
+    pub join_type: JoinType,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub id: NodeId,
+    // From the start of the first operand to the end of the last, not including any parentheses around the search
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub span: Span
+}
+
+impl<'a> Search<'a> {
+    pub fn new(comparisons: Vec<ComparisonOrSearch<'a>>, join_type: JoinType) -> Self {
+        Self { comparisons, join_type, id: NodeId::default(), span: Span::default() }
+    }
+}
+
+impl PartialEq for Search<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.comparisons == other.comparisons && self.join_type == other.join_type
+    }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Search {
-    pub comparisons: LinkedList<ComparisonOrSearch>,
-    pub join_type: JoinType
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "type", rename_all = "snake_case"))]
+pub enum ComparisonOrSearch<'a> {
+    Comparison(Comparison<'a>),
+    Search(Search<'a>)
 }
 
-#[derive(Debug, PartialEq)]
-pub enum ComparisonOrSearch {
-    Comparison(Comparison),
-    Search(Search)
+impl ComparisonOrSearch<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            ComparisonOrSearch::Comparison(comparison) => comparison.span,
+            ComparisonOrSearch::Search(search) => search.span
+        }
+    }
 }
 
-pub fn parse(tokens: LinkedList<TokenData>) -> Result<Search, String> {
-    let mut tokens = to_postfix(tokens);
+// Identifies a node of a `Search` by its position in a preorder walk of the tree when it was numbered: the root search is
+// 0, its first operand is 1, that operand's first operand (if it's a search) is 2, and so on. Each node stores its own
+// ID, so a pass holding a node can refer to it without a walk, and `NodeTable` looks one up by ID. Rearranging the tree
+// (e.g. `eval::optimize`) keeps the nodes' IDs, so they still refer to the same comparisons and searches.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeId(pub usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Node<'s, 'a> {
+    Comparison(&'s Comparison<'a>),
+    Search(&'s Search<'a>)
+}
 
-    if let Some(comparison_or_search) = _parse(&mut tokens)? {
-        match comparison_or_search {
-            ComparisonOrSearch::Search(search) => Ok(search),
-            comparison@ _ => Ok(Search { comparisons: LinkedList::from([comparison]), join_type: JoinType::And })
+impl Node<'_, '_> {
+    pub fn id(&self) -> NodeId {
+        match self {
+            Node::Comparison(comparison) => comparison.id,
+            Node::Search(search) => search.id
         }
+    }
+}
 
-    } else {
-        Ok(Search { comparisons: LinkedList::new(), join_type: JoinType::And })
+impl<'s, 'a> From<&'s ComparisonOrSearch<'a>> for Node<'s, 'a> {
+    fn from(operand: &'s ComparisonOrSearch<'a>) -> Self {
+        match operand {
+            ComparisonOrSearch::Comparison(comparison) => Node::Comparison(comparison),
+            ComparisonOrSearch::Search(search) => Node::Search(search)
+        }
     }
 }
 
-fn _parse(tokens: &mut LinkedList<TokenData>) -> Result<Option<ComparisonOrSearch>, String> {
-    if tokens.is_empty() {
-        return Ok(None);
+impl<'a> Search<'a> {
+    // Walks every node in preorder, with its ID
+    pub fn nodes(&self) -> Nodes<'_, 'a> {
+        Nodes { stack: vec![Node::Search(self)] }
     }
 
-    match tokens.pop_back().unwrap().token {
-        Token::JoinType(join_type) => {
-            let right_tree = _parse(tokens)?;
-            let left_tree = _parse(tokens)?;
+    // Gives every node its preorder position as its ID. `parse` numbers the trees it returns.
+    pub fn number_nodes(&mut self) {
+        self.id = NodeId(0);
+        let mut next_id = 1;
+        let mut stack: Vec<&mut ComparisonOrSearch<'a>> = self.comparisons.iter_mut().rev().collect();
+
+        while let Some(operand) = stack.pop() {
+            match operand {
+                ComparisonOrSearch::Comparison(comparison) => comparison.id = NodeId(next_id),
+                ComparisonOrSearch::Search(search) => {
+                    search.id = NodeId(next_id);
+                    stack.extend(search.comparisons.iter_mut().rev());
+                }
+            }
+            next_id += 1;
+        }
+    }
 
-            
-            let mut search = Search{
-                join_type,
-                comparisons: LinkedList::new()
-            };
-            merge_subtree(&mut search, left_tree);
-            merge_subtree(&mut search, right_tree);
-
-            Ok(Some(ComparisonOrSearch::Search(search)))
+    pub fn node_table(&self) -> NodeTable<'_, 'a> {
+        NodeTable::new(self)
+    }
+}
+
+pub struct Nodes<'s, 'a> {
+    stack: Vec<Node<'s, 'a>>
+}
+
+impl<'s, 'a> Iterator for Nodes<'s, 'a> {
+    type Item = (NodeId, Node<'s, 'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if let Node::Search(search) = node {
+            self.stack.extend(search.comparisons.iter().rev().map(Node::from));
         }
+        Some((node.id(), node))
+    }
+}
+
+// The nodes of a `Search` indexed by their IDs, for passes that look nodes up repeatedly. Building it walks the tree
+// once, and each lookup after that is constant time.
+pub struct NodeTable<'s, 'a> {
+    nodes: Vec<Option<Node<'s, 'a>>>
+}
+
+impl<'s, 'a> NodeTable<'s, 'a> {
+    pub fn new(search: &'s Search<'a>) -> Self {
+        let mut nodes = Vec::new();
+        for (id, node) in search.nodes() {
+            if id.0 >= nodes.len() {
+                nodes.resize(id.0 + 1, None);
+            }
+            nodes[id.0] = Some(node);
+        }
+        Self { nodes }
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<Node<'s, 'a>> {
+        self.nodes.get(id.0).copied().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
 
-        Token::Value(value) => {
-            let Token::Comparator(comparator) = tokens.pop_back().unwrap().token else { panic!("Expected comparator") };
-            let Token::Name(name) = tokens.pop_back().unwrap().token else { panic!("Expected name") };
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+pub fn parse<'a>(tokens: Vec<TokenData<'a>>) -> Result<Search<'a>, String> {
+    // `to_postfix` assumes the parentheses are in place, so they're checked first
+    check_parens(&tokens).map_err(|error| error.message)?;
+
+    // Evaluate the postfix tokens with a stack of operands rather than recursively, so the depth of the tree (e.g. a
+    // long chain of joins) can't overflow the call stack
+    let mut operands: Vec<ComparisonOrSearch<'a>> = Vec::new();
+    let mut tokens = to_postfix(tokens).into_iter();
 
-            Ok(Some(ComparisonOrSearch::Comparison(Comparison {
-                name,
-                comparator,
-                value
-            })))
+    while let Some(token) = tokens.next() {
+        match token.token {
+            Token::JoinType(join_type) => {
+                let right_tree = operands.pop();
+                let left_tree = operands.pop();
+
+                // Joins are left-associative, so a chain like `a | b | c` builds up in the left subtree. Extending that
+                // search instead of copying its comparisons into a new one keeps long chains linear.
+                let mut search = match left_tree {
+                    Some(ComparisonOrSearch::Search(search)) if search.join_type == join_type => search,
+                    left_tree => {
+                        let mut search = Search::new(Vec::new(), join_type);
+                        merge_subtree(&mut search, left_tree);
+                        search
+                    }
+                };
+                merge_subtree(&mut search, right_tree);
+
+                operands.push(ComparisonOrSearch::Search(search));
+            }
+
+            Token::Name(name) => {
+                let name_span = token.span;
+                let comparator = tokens.next().map(|token| (token.token, token.span));
+                let value = tokens.next().map(|token| (token.token, token.span));
+
+                match (comparator, value) {
+                    (Some((Token::Comparator(comparator), comparator_span)), Some((Token::Value(value), value_span))) =>
+                        operands.push(ComparisonOrSearch::Comparison(Comparison {
+                            name,
+                            comparator,
+                            value,
+                            id: NodeId::default(),
+                            span: name_span.to(value_span),
+                            name_span,
+                            comparator_span,
+                            value_span
+                        })),
+                    (Some((Token::Comparator(_), _)), token) => return Err(format!("Expected a value but found {:?}", token.map(|(token, _)| token))),
+                    (token, _) => return Err(format!("Expected a comparator but found {:?}", token.map(|(token, _)| token)))
+                }
+            }
+
+            token => return Err(format!("Unexpected token {:?}", token))
         }
+    }
 
-        token @ _ => Err(format!("Unexpected token {:?}", token).to_string())
+    if operands.len() > 1 {
+        return Err("Expected `&`, `|` or `^` between comparisons".to_string());
     }
+
+    let mut search = match operands.pop() {
+        Some(ComparisonOrSearch::Search(search)) => search,
+        Some(comparison) => {
+            let span = comparison.span();
+            Search { comparisons: vec![comparison], join_type: JoinType::And, id: NodeId::default(), span }
+        },
+        None => Search::new(Vec::new(), JoinType::And)
+    };
+    search.number_nodes();
+    Ok(search)
 }
 
-fn merge_subtree(search: &mut Search, subtree: Option<ComparisonOrSearch>) {
+fn merge_subtree<'a>(search: &mut Search<'a>, subtree: Option<ComparisonOrSearch<'a>>) {
     if let Some(mut subtree) = subtree {
+        search.span = if search.comparisons.is_empty() { subtree.span() } else { search.span.to(subtree.span()) };
         if let ComparisonOrSearch::Search(ref mut subsearch) = subtree {
             if subsearch.join_type == search.join_type {
                 search.comparisons.append(&mut subsearch.comparisons);
             }
             else {
-                search.comparisons.push_back(subtree);
+                search.comparisons.push(subtree);
             }
         }
         else {
-            search.comparisons.push_back(subtree);
+            search.comparisons.push(subtree);
         }
     }
 }
 
+// Finds the first parenthesis out of place, which `to_postfix` can't make sense of, pointing at it
+pub fn check_parens(tokens: &[TokenData]) -> Result<(), FilterError> {
+    // Like in `to_postfix`, the start of the filter acts like a join
+    let mut after_join = true;
+    let mut open = Vec::new();
+
+    for token in tokens {
+        match token.token {
+            Token::OpenParen if !after_join => return Err(FilterError::new("Expected `&`, `|` or `^` before `(`".to_string(), token.span)),
+            Token::OpenParen => open.push(token.span),
+            Token::CloseParen if after_join => return Err(FilterError::new("Expected a comparison before `)`".to_string(), token.span)),
+            Token::CloseParen => if open.pop().is_none() {
+                return Err(FilterError::new("`)` has no matching `(`".to_string(), token.span));
+            },
+            Token::JoinType(_) => after_join = true,
+            _ => after_join = false
+        }
+    }
 
-fn to_postfix(mut tokens: LinkedList<TokenData>) -> LinkedList<TokenData> {
-    let mut last_was_join = false;
-    let mut postfix = LinkedList::new();
-    let mut operator_stack = LinkedList::new();
+    match open.pop() {
+        Some(span) => Err(FilterError::new("`(` is never closed".to_string(), span)),
+        None => Ok(())
+    }
+}
 
-    while !tokens.is_empty() {
-        let token = tokens.pop_front().unwrap();
+// The parentheses in `tokens` must have passed `check_parens`
+fn to_postfix<'a>(tokens: Vec<TokenData<'a>>) -> Vec<TokenData<'a>> {
+    // The start of the filter acts like a join: it may be followed by an open parentheses but not a close parentheses
+    let mut last_was_join = true;
+    let mut postfix = Vec::with_capacity(tokens.len());
+    let mut operator_stack = Vec::new();
 
+    for token in tokens {
         match &token.token {
             Token::OpenParen => {
                 if !last_was_join {
-                    panic!("Expected operator but found open parentheses");
+                    unreachable!("Expected operator but found open parentheses, which `check_parens` rules out");
                 }
-                operator_stack.push_front(token);
+                operator_stack.push(token);
             },
             Token::CloseParen => {
                 if last_was_join {
-                    panic!("Unexpected close parentheses after operator");
+                    unreachable!("Unexpected close parentheses after operator, which `check_parens` rules out");
                 }
 
                 loop {
-                    let Some(operator) = operator_stack.pop_front() else {
-                        panic!("Close parentheses was found without a preceding open parentheses");
+                    let Some(operator) = operator_stack.pop() else {
+                        unreachable!("Close parentheses was found without a preceding open parentheses, which `check_parens` rules out");
                     };
                     match &operator.token {
-                        Token::JoinType(_) => { postfix.push_back(operator) },
+                        Token::JoinType(_) => { postfix.push(operator) },
                         Token::OpenParen => break,
-                        _ => panic!("Invalid token {:?} found in operator stack", operator) 
+                        _ => unreachable!("Invalid token {:?} found in operator stack, which only holds joins and `(`", operator)
                     }
                 }
             },
             Token::JoinType(join_type) => {
                 last_was_join = true;
-                while let Some(operator) = operator_stack.front() {
+                while let Some(operator) = operator_stack.last() {
                     match &operator.token {
                         Token::JoinType(operator) => {
                             if operator < join_type {
                                 break;
                             }
                             // Now know that the operator at the stack's top is higher precedence than the new operator, meaning we want to move
-                            // it to `postfix`, so we can now safely remove it from `operator_stack` instead of just using `.front()`. Also
-                            // needed to actually let us perform a move into the `postfix` LinkedList.
-                            let operator = operator_stack.pop_front().unwrap();
-                            postfix.push_back(operator);
+                            // it to `postfix`, so we can now safely remove it from `operator_stack` instead of just using `.last()`. Also
+                            // needed to actually let us perform a move into the `postfix` Vec.
+                            let operator = operator_stack.pop().unwrap();
+                            postfix.push(operator);
                         }
                         Token::OpenParen => {
                             // Everything inside parentheses should have higher precedence than the stuff outside
                             break;
                         },
-                        _ => panic!("Invalid token {:?} found in operator stack", operator)
+                        _ => unreachable!("Invalid token {:?} found in operator stack, which only holds joins and `(`", operator)
                     }
                 }
-                operator_stack.push_front(token);
+                operator_stack.push(token);
             },
             _ => {
                 last_was_join = false;
-                postfix.push_back(token);
+                postfix.push(token);
             }
         }
     }
 
-    while !operator_stack.is_empty() {
-        let next_op = operator_stack.pop_front().unwrap();
+    while let Some(next_op) = operator_stack.pop() {
         match &next_op.token {
-            Token::JoinType(_) => postfix.push_back(next_op),
-            Token::OpenParen => panic!("Unclosed parentheses!"),
-            _ => panic!("Invalid token {:?} found in operator stack", next_op)
+            Token::JoinType(_) => postfix.push(next_op),
+            Token::OpenParen => unreachable!("Unclosed parentheses, which `check_parens` rules out"),
+            _ => unreachable!("Invalid token {:?} found in operator stack, which only holds joins and `(`", next_op)
         }
     }
 
@@ -163,20 +382,27 @@ fn to_postfix(mut tokens: LinkedList<TokenData>) -> LinkedList<TokenData> {
 
 #[cfg(test)]
 mod parser_tests {
+    use crate::types::{Position, Span};
     use super::*;
+
+    fn at(offset: usize, line: usize, col: usize) -> Position {
+        Position { byte: offset, char: offset, utf16: offset, line, col }
+    }
     // Note: to_postfix_tests module ensures that order of operations & parentheses are handled correctly. No need to include tests for those features here.
 
     #[test]
     fn parses_single_comparison() {
-        let input = LinkedList::from([ 
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 }
-        ]);
-
-        let expected = LinkedList::from([ ComparisonOrSearch::Comparison(Comparison{
-            name: "test".to_string(), comparator: Comparator::Equal, value: Value::String("test".to_string())
-        })]);
+        let input = vec![ 
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) }
+        ];
+
+        let expected = vec![ ComparisonOrSearch::Comparison(Comparison::new(
+            "test".into(),
+            Comparator::Equal,
+            Value::String("test".into())
+        ))];
         let result = parse(input);
 
         assert!(result.is_ok());
@@ -187,25 +413,25 @@ mod parser_tests {
 
     #[test]
     fn parses_single_join() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
-
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 34 },
-        ]);
-
-        let expected = Search {
-            comparisons: LinkedList::from([
-                ComparisonOrSearch::Comparison(Comparison{ name: "test".to_string(), comparator: Comparator::Equal, value: Value::String("test".to_string()) }),
-                ComparisonOrSearch::Comparison(Comparison{ name: "test_2".to_string(), comparator: Comparator::Equal, value: Value::Number(12_345.67) })
-            ]),
-            join_type: JoinType::Or
-        };
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
+
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(25, 0, 25), at(34, 0, 34)) },
+        ];
+
+        let expected = Search::new(
+            vec![
+                ComparisonOrSearch::Comparison(Comparison::new("test".into(), Comparator::Equal, Value::String("test".into()))),
+                ComparisonOrSearch::Comparison(Comparison::new("test_2".into(), Comparator::Equal, Value::Number(12_345.67)))
+            ],
+            JoinType::Or
+        );
         let result = parse(input);
 
         assert!(result.is_ok());
@@ -216,39 +442,39 @@ mod parser_tests {
     
     #[test]
     fn combines_repeated_joins() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
             
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(55, 0, 55), at(56, 0, 56)) },
             
-            TokenData{ token: Token::Name("test_4".to_string()), source: "test_4".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_4".to_string())), source: "\"test_4\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
-
-        let expected = Search {
-            comparisons: LinkedList::from([
-                ComparisonOrSearch::Comparison(Comparison{ name: "test".to_string(), comparator: Comparator::Equal, value: Value::String("test".to_string()) }),
-                ComparisonOrSearch::Comparison(Comparison{ name: "test_2".to_string(), comparator: Comparator::Equal, value: Value::String("test_2".to_string()) }),
-                ComparisonOrSearch::Comparison(Comparison{ name: "test_3".to_string(), comparator: Comparator::Equal, value: Value::Number(12_345.67) }),
-                ComparisonOrSearch::Comparison(Comparison{ name: "test_4".to_string(), comparator: Comparator::Equal, value: Value::String("test_4".to_string()) })
-            ]),
-            join_type: JoinType::And
-        };
+            TokenData{ token: Token::Name("test_4".into()), source: "test_4".into(), span: Span::new(at(57, 0, 57), at(63, 0, 63)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(64, 0, 64), at(65, 0, 65)) },
+            TokenData{ token: Token::Value(Value::String("test_4".into())), source: "\"test_4\"".into(), span: Span::new(at(66, 0, 66), at(74, 0, 74)) }
+        ];
+
+        let expected = Search::new(
+            vec![
+                ComparisonOrSearch::Comparison(Comparison::new("test".into(), Comparator::Equal, Value::String("test".into()))),
+                ComparisonOrSearch::Comparison(Comparison::new("test_2".into(), Comparator::Equal, Value::String("test_2".into()))),
+                ComparisonOrSearch::Comparison(Comparison::new("test_3".into(), Comparator::Equal, Value::Number(12_345.67))),
+                ComparisonOrSearch::Comparison(Comparison::new("test_4".into(), Comparator::Equal, Value::String("test_4".into())))
+            ],
+            JoinType::And
+        );
         let result = parse(input);
 
         assert!(result.is_ok());
@@ -259,49 +485,49 @@ mod parser_tests {
 
     #[test]
     fn parses_balanced_nested_join() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
-
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
-
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
-
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-
-            TokenData{ token: Token::Name("test_4".to_string()), source: "test_4".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_4".to_string())), source: "\"test_4\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
-
-        let expected = Search {
-            comparisons: LinkedList::from([
-                ComparisonOrSearch::Search(Search {
-                    comparisons: LinkedList::from([
-                        ComparisonOrSearch::Comparison(Comparison{ name: "test".to_string(), comparator: Comparator::Equal, value: Value::String("test".to_string()) }),
-                        ComparisonOrSearch::Comparison(Comparison{ name: "test_2".to_string(), comparator: Comparator::Equal, value: Value::String("test_2".to_string()) })
-                    ]),
-                    join_type: JoinType::And
-                }),
-                ComparisonOrSearch::Search(Search {
-                    comparisons: LinkedList::from([
-                        ComparisonOrSearch::Comparison(Comparison{ name: "test_3".to_string(), comparator: Comparator::Equal, value: Value::Number(12_345.67) }),
-                        ComparisonOrSearch::Comparison(Comparison{ name: "test_4".to_string(), comparator: Comparator::Equal, value: Value::String("test_4".to_string()) })
-                    ]),
-                    join_type: JoinType::And
-                })
-            ]),
-            join_type: JoinType::Or
-        };
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
+
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
+
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
+
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
+
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(55, 0, 55), at(56, 0, 56)) },
+
+            TokenData{ token: Token::Name("test_4".into()), source: "test_4".into(), span: Span::new(at(57, 0, 57), at(63, 0, 63)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(64, 0, 64), at(65, 0, 65)) },
+            TokenData{ token: Token::Value(Value::String("test_4".into())), source: "\"test_4\"".into(), span: Span::new(at(66, 0, 66), at(74, 0, 74)) }
+        ];
+
+        let expected = Search::new(
+            vec![
+                ComparisonOrSearch::Search(Search::new(
+                    vec![
+                        ComparisonOrSearch::Comparison(Comparison::new("test".into(), Comparator::Equal, Value::String("test".into()))),
+                        ComparisonOrSearch::Comparison(Comparison::new("test_2".into(), Comparator::Equal, Value::String("test_2".into())))
+                    ],
+                    JoinType::And
+                )),
+                ComparisonOrSearch::Search(Search::new(
+                    vec![
+                        ComparisonOrSearch::Comparison(Comparison::new("test_3".into(), Comparator::Equal, Value::Number(12_345.67))),
+                        ComparisonOrSearch::Comparison(Comparison::new("test_4".into(), Comparator::Equal, Value::String("test_4".into())))
+                    ],
+                    JoinType::And
+                ))
+            ],
+            JoinType::Or
+        );
         let result = parse(input);
 
         assert!(result.is_ok());
@@ -312,44 +538,44 @@ mod parser_tests {
     
     #[test]
     fn parses_imbalanced_nested_join() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
-
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
-
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
-
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-
-            TokenData{ token: Token::Name("test_4".to_string()), source: "test_4".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_4".to_string())), source: "\"test_4\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
-
-        let expected = Search {
-            comparisons: LinkedList::from([
-                ComparisonOrSearch::Comparison(Comparison{ name: "test".to_string(), comparator: Comparator::Equal, value: Value::String("test".to_string()) }),
-                ComparisonOrSearch::Search(Search {
-                    comparisons: LinkedList::from([
-                        ComparisonOrSearch::Comparison(Comparison{ name: "test_2".to_string(), comparator: Comparator::Equal, value: Value::String("test_2".to_string()) }),
-                        ComparisonOrSearch::Comparison(Comparison{ name: "test_3".to_string(), comparator: Comparator::Equal, value: Value::Number(12_345.67) }),
-                        ComparisonOrSearch::Comparison(Comparison{ name: "test_4".to_string(), comparator: Comparator::Equal, value: Value::String("test_4".to_string()) })
-                    ]),
-                    join_type: JoinType::And
-                })
-            ]),
-            join_type: JoinType::Or
-        };
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
+
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
+
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
+
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
+
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(55, 0, 55), at(56, 0, 56)) },
+
+            TokenData{ token: Token::Name("test_4".into()), source: "test_4".into(), span: Span::new(at(57, 0, 57), at(63, 0, 63)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(64, 0, 64), at(65, 0, 65)) },
+            TokenData{ token: Token::Value(Value::String("test_4".into())), source: "\"test_4\"".into(), span: Span::new(at(66, 0, 66), at(74, 0, 74)) }
+        ];
+
+        let expected = Search::new(
+            vec![
+                ComparisonOrSearch::Comparison(Comparison::new("test".into(), Comparator::Equal, Value::String("test".into()))),
+                ComparisonOrSearch::Search(Search::new(
+                    vec![
+                        ComparisonOrSearch::Comparison(Comparison::new("test_2".into(), Comparator::Equal, Value::String("test_2".into()))),
+                        ComparisonOrSearch::Comparison(Comparison::new("test_3".into(), Comparator::Equal, Value::Number(12_345.67))),
+                        ComparisonOrSearch::Comparison(Comparison::new("test_4".into(), Comparator::Equal, Value::String("test_4".into())))
+                    ],
+                    JoinType::And
+                ))
+            ],
+            JoinType::Or
+        );
         let result = parse(input);
 
         assert!(result.is_ok());
@@ -357,40 +583,97 @@ mod parser_tests {
         let result = result.unwrap();
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn records_spans_of_nodes() {
+        let input = "a = 1 & (bé >= \"x\" | c != 2)\n& d < 3";
+        let search = parse(crate::lexer::lex(input).0).unwrap();
+
+        assert_eq!(search.span.slice(input), input);
+
+        let ComparisonOrSearch::Comparison(a) = &search.comparisons[0] else { panic!("Expected a comparison") };
+        assert_eq!(a.span.slice(input), "a = 1");
+
+        let ComparisonOrSearch::Search(or) = &search.comparisons[1] else { panic!("Expected a search") };
+        assert_eq!(or.span.slice(input), "bé >= \"x\" | c != 2");
+
+        let ComparisonOrSearch::Comparison(b) = &or.comparisons[0] else { panic!("Expected a comparison") };
+        assert_eq!(b.name_span.slice(input), "bé");
+        assert_eq!(b.comparator_span.slice(input), ">=");
+        assert_eq!(b.value_span.slice(input), "\"x\"");
+        assert_eq!(b.span.slice(input), "bé >= \"x\"");
+
+        assert_eq!(search.comparisons[2].span().slice(input), "d < 3");
+        assert_eq!(search.comparisons[2].span().start.line, 1);
+    }
+
+    #[test]
+    fn ignores_spans_when_comparing_nodes() {
+        let parsed = parse(crate::lexer::lex("  a = 1").0).unwrap();
+        let built = Search::new(vec![ComparisonOrSearch::Comparison(Comparison::new("a".into(), Comparator::Equal, Value::Number(1.)))], JoinType::And);
+
+        assert_ne!(parsed.span, built.span);
+        assert_eq!(parsed, built);
+    }
+
+    #[test]
+    fn errors_on_misplaced_parens_instead_of_panicking() {
+        for (input, message, at) in [
+            ("a = 1 & (b = 2", "`(` is never closed", "("),
+            ("a = 1 (b = 2)", "Expected `&`, `|` or `^` before `(`", "("),
+            ("(a = 1 &)", "Expected a comparison before `)`", ")"),
+            ("()", "Expected a comparison before `)`", ")"),
+            ("a = 1) & (b = 2", "`)` has no matching `(`", ")")
+        ] {
+            let tokens = crate::lexer::lex(input).0;
+            let error = check_parens(&tokens).unwrap_err();
+            assert_eq!(error.message, message, "{}", input);
+            assert_eq!(error.span.start.byte, input.find(at).unwrap(), "{}", input);
+
+            assert_eq!(parse(tokens), Err(message.to_string()));
+        }
+
+        assert_eq!(check_parens(&crate::lexer::lex("((a = 1) | (b = 2)) & c = 3").0), Ok(()));
+    }
 }
 
 #[cfg(test)]
 mod to_postfix_tests {
+    use crate::types::{Position, Span};
     use super::*;
 
+    fn at(offset: usize, line: usize, col: usize) -> Position {
+        Position { byte: offset, char: offset, utf16: offset, line, col }
+    }
+
     #[test]
     fn leaves_comparisons_alone() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 }
-        ]);
-
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 }
-        ]);
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) }
+        ];
+
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
         
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 16 }
-        ]);
-
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 16 }
-        ]);
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(7, 0, 7), at(16, 0, 16)) }
+        ];
+
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(7, 0, 7), at(16, 0, 16)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -398,29 +681,29 @@ mod to_postfix_tests {
 
     #[test]
     fn moves_single_join_type_to_end() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
-        ]);
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -428,40 +711,40 @@ mod to_postfix_tests {
 
     #[test]
     fn gives_and_precedence_over_or() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) }
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 }
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -469,41 +752,41 @@ mod to_postfix_tests {
 
     #[test]
     fn gives_and_precedence_over_or_2() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) }
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 }
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -511,41 +794,41 @@ mod to_postfix_tests {
 
     #[test]
     fn gives_xor_precedence_over_and() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) }
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
             
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 }
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -553,40 +836,40 @@ mod to_postfix_tests {
 
     #[test]
     fn gives_xor_precedence_over_and_2() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) }
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 22 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 23, end: 0, end_line: 0, end_col: 24 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 25, end: 0, end_line: 0, end_col: 33 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 34, end: 0, end_line: 0, end_col: 35 },
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 }
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -594,43 +877,43 @@ mod to_postfix_tests {
 
     #[test]
     fn parentheses_override_precedence_and_over_or() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
             
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 17 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
             
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
             
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
             
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
 
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(57, 0, 57), at(58, 0, 58)) }
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
             
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
     
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 }
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -638,43 +921,88 @@ mod to_postfix_tests {
 
     #[test]
     fn parentheses_override_precedence_xor_over_and() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 17 },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
 
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(57, 0, 57), at(58, 0, 58)) }
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 }
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) }
+        ];
+        let result = to_postfix(input);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn allows_leading_open_parentheses() {
+        let input = vec![
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(0, 0, 0), at(1, 0, 1)) },
+
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(2, 0, 2), at(6, 0, 6)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(7, 0, 7), at(8, 0, 8)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(9, 0, 9), at(15, 0, 15)) },
+
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
+
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::Number(2.)), source: "2".into(), span: Span::new(at(27, 0, 27), at(28, 0, 28)) },
+
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(29, 0, 29), at(30, 0, 30)) },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(31, 0, 31), at(32, 0, 32)) },
+
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(33, 0, 33), at(39, 0, 39)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(40, 0, 40), at(41, 0, 41)) },
+            TokenData{ token: Token::Value(Value::Number(3.)), source: "3".into(), span: Span::new(at(42, 0, 42), at(43, 0, 43)) },
+        ];
+
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(2, 0, 2), at(6, 0, 6)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(7, 0, 7), at(8, 0, 8)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(9, 0, 9), at(15, 0, 15)) },
+
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::Number(2.)), source: "2".into(), span: Span::new(at(27, 0, 27), at(28, 0, 28)) },
+
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
+
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(33, 0, 33), at(39, 0, 39)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(40, 0, 40), at(41, 0, 41)) },
+            TokenData{ token: Token::Value(Value::Number(3.)), source: "3".into(), span: Span::new(at(42, 0, 42), at(43, 0, 43)) },
+
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(31, 0, 31), at(32, 0, 32)) },
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -682,83 +1010,83 @@ mod to_postfix_tests {
 
     #[test]
     fn correctly_transforms_complex_expressions() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
             
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 17 },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
             
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
             
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
             
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
             
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-
-            TokenData{ token: Token::Name("test_4".to_string()), source: "test_4".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::GreaterThan), source: ">".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_4".to_string())), source: "\"test_4\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(57, 0, 57), at(58, 0, 58)) },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(59, 0, 59), at(60, 0, 60)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(61, 0, 61), at(62, 0, 62)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(63, 0, 63), at(64, 0, 64)) },
+
+            TokenData{ token: Token::Name("test_4".into()), source: "test_4".into(), span: Span::new(at(65, 0, 65), at(71, 0, 71)) },
+            TokenData{ token: Token::Comparator(Comparator::GreaterThan), source: ">".into(), span: Span::new(at(72, 0, 72), at(73, 0, 73)) },
+            TokenData{ token: Token::Value(Value::String("test_4".into())), source: "\"test_4\"".into(), span: Span::new(at(74, 0, 74), at(82, 0, 82)) },
             
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(83, 0, 83), at(84, 0, 84)) },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(85, 0, 85), at(86, 0, 86)) },
 
-            TokenData{ token: Token::Name("test_5".to_string()), source: "test_5".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_5".to_string())), source: "\"test_5\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_5".into()), source: "test_5".into(), span: Span::new(at(87, 0, 87), at(93, 0, 93)) },
+            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".into(), span: Span::new(at(94, 0, 94), at(96, 0, 96)) },
+            TokenData{ token: Token::Value(Value::String("test_5".into())), source: "\"test_5\"".into(), span: Span::new(at(97, 0, 97), at(105, 0, 105)) },
 
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(106, 0, 106), at(107, 0, 107)) },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(108, 0, 108), at(109, 0, 109)) },
 
-            TokenData{ token: Token::Name("test_6".to_string()), source: "test_6".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_6".to_string())), source: "\"test_6\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-        ]);
+            TokenData{ token: Token::Name("test_6".into()), source: "test_6".into(), span: Span::new(at(110, 0, 110), at(116, 0, 116)) },
+            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".into(), span: Span::new(at(117, 0, 117), at(119, 0, 119)) },
+            TokenData{ token: Token::Value(Value::String("test_6".into())), source: "\"test_6\"".into(), span: Span::new(at(120, 0, 120), at(128, 0, 128)) },
+        ];
 
-        let expected = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let expected = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_4".to_string()), source: "test_4".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::GreaterThan), source: ">".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_4".to_string())), source: "\"test_4\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_4".into()), source: "test_4".into(), span: Span::new(at(65, 0, 65), at(71, 0, 71)) },
+            TokenData{ token: Token::Comparator(Comparator::GreaterThan), source: ">".into(), span: Span::new(at(72, 0, 72), at(73, 0, 73)) },
+            TokenData{ token: Token::Value(Value::String("test_4".into())), source: "\"test_4\"".into(), span: Span::new(at(74, 0, 74), at(82, 0, 82)) },
 
-            TokenData{ token: Token::Name("test_5".to_string()), source: "test_5".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_5".to_string())), source: "\"test_5\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_5".into()), source: "test_5".into(), span: Span::new(at(87, 0, 87), at(93, 0, 93)) },
+            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".into(), span: Span::new(at(94, 0, 94), at(96, 0, 96)) },
+            TokenData{ token: Token::Value(Value::String("test_5".into())), source: "\"test_5\"".into(), span: Span::new(at(97, 0, 97), at(105, 0, 105)) },
 
 
-            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::Xor), source: "^".into(), span: Span::new(at(85, 0, 85), at(86, 0, 86)) },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(59, 0, 59), at(60, 0, 60)) },
 
-            TokenData{ token: Token::Name("test_6".to_string()), source: "test_6".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_6".to_string())), source: "\"test_6\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_6".into()), source: "test_6".into(), span: Span::new(at(110, 0, 110), at(116, 0, 116)) },
+            TokenData{ token: Token::Comparator(Comparator::GreaterThanOrEqual), source: ">=".into(), span: Span::new(at(117, 0, 117), at(119, 0, 119)) },
+            TokenData{ token: Token::Value(Value::String("test_6".into())), source: "\"test_6\"".into(), span: Span::new(at(120, 0, 120), at(128, 0, 128)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(108, 0, 108), at(109, 0, 109)) }
+        ];
         let result = to_postfix(input);
 
         assert_eq!(result, expected);
@@ -767,25 +1095,25 @@ mod to_postfix_tests {
     #[test]
     #[should_panic(expected = "without a preceding open")]
     fn panics_if_given_close_paren_without_open() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(16, 0, 16), at(22, 0, 22)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(23, 0, 23), at(24, 0, 24)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(25, 0, 25), at(33, 0, 33)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(34, 0, 34), at(35, 0, 35)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(36, 0, 36), at(42, 0, 42)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(43, 0, 43), at(44, 0, 44)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(45, 0, 45), at(54, 0, 54)) },
 
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(55, 0, 55), at(56, 0, 56)) }
+        ];
 
         to_postfix(input);
     }
@@ -793,24 +1121,24 @@ mod to_postfix_tests {
     #[test]
     #[should_panic(expected = "Unclosed")]
     fn panics_if_given_open_paren_without_close() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 17 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-        ]);
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
+        ];
 
         to_postfix(input);
     }
@@ -818,26 +1146,26 @@ mod to_postfix_tests {
     #[test]
     #[should_panic(expected = "Unclosed")]
     fn panics_on_bad_nested_parens() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
-
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 17 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 18, end: 0, end_line: 0, end_col: 19 },
-
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
+
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(18, 0, 18), at(19, 0, 19)) },
+
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(20, 0, 20), at(26, 0, 26)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(27, 0, 27), at(28, 0, 28)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(29, 0, 29), at(37, 0, 37)) },
             
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(38, 0, 38), at(39, 0, 39)) },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(40, 0, 40), at(41, 0, 41)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-        ]);
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(42, 0, 42), at(48, 0, 48)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(49, 0, 49), at(50, 0, 50)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(51, 0, 51), at(60, 0, 60)) },
+        ];
 
         to_postfix(input);
     }
@@ -845,26 +1173,26 @@ mod to_postfix_tests {
     #[test]
     #[should_panic(expected = "Expected operator")]
     fn panics_on_out_of_order_open_parentheses() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 17 },
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(38, 0, 38), at(44, 0, 44)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(45, 0, 45), at(46, 0, 46)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(47, 0, 47), at(56, 0, 56)) },
 
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 }
-        ]);
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(57, 0, 57), at(58, 0, 58)) }
+        ];
 
         to_postfix(input);
     }
@@ -872,26 +1200,82 @@ mod to_postfix_tests {
     #[test]
     #[should_panic(expected = "Unexpected close")]
     fn panics_on_out_of_order_close_parentheses() {
-        let input = LinkedList::from([
-            TokenData{ token: Token::Name("test".to_string()), source: "test".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 4 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 5, end: 0, end_line: 0, end_col: 6 },
-            TokenData{ token: Token::Value(Value::String("test".to_string())), source: "\"test\"".to_string(), start: 0, start_line: 0, start_col: 7, end: 0, end_line: 0, end_col: 13 },
+        let input = vec![
+            TokenData{ token: Token::Name("test".into()), source: "test".into(), span: Span::new(at(0, 0, 0), at(4, 0, 4)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(5, 0, 5), at(6, 0, 6)) },
+            TokenData{ token: Token::Value(Value::String("test".into())), source: "\"test\"".into(), span: Span::new(at(7, 0, 7), at(13, 0, 13)) },
 
-            TokenData{ token: Token::JoinType(JoinType::And), source: "&".to_string(), start: 0, start_line: 0, start_col: 14, end: 0, end_line: 0, end_col: 15 },
-            TokenData{ token: Token::OpenParen, source: "(".to_string(), start: 0, start_line: 0, start_col: 16, end: 0, end_line: 0, end_col: 17 },
+            TokenData{ token: Token::JoinType(JoinType::And), source: "&".into(), span: Span::new(at(14, 0, 14), at(15, 0, 15)) },
+            TokenData{ token: Token::OpenParen, source: "(".into(), span: Span::new(at(16, 0, 16), at(17, 0, 17)) },
 
-            TokenData{ token: Token::Name("test_2".to_string()), source: "test_2".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::String("test_2".to_string())), source: "\"test_2\"".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::Name("test_2".into()), source: "test_2".into(), span: Span::new(at(18, 0, 18), at(24, 0, 24)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(25, 0, 25), at(26, 0, 26)) },
+            TokenData{ token: Token::Value(Value::String("test_2".into())), source: "\"test_2\"".into(), span: Span::new(at(27, 0, 27), at(35, 0, 35)) },
 
-            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::CloseParen, source: ")".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
+            TokenData{ token: Token::JoinType(JoinType::Or), source: "|".into(), span: Span::new(at(36, 0, 36), at(37, 0, 37)) },
+            TokenData{ token: Token::CloseParen, source: ")".into(), span: Span::new(at(38, 0, 38), at(39, 0, 39)) },
 
-            TokenData{ token: Token::Name("test_3".to_string()), source: "test_3".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".to_string(), start: 0, start_line: 0, start_col: 0, end: 0, end_line: 0, end_col: 0 },
-        ]);
+            TokenData{ token: Token::Name("test_3".into()), source: "test_3".into(), span: Span::new(at(40, 0, 40), at(46, 0, 46)) },
+            TokenData{ token: Token::Comparator(Comparator::Equal), source: "=".into(), span: Span::new(at(47, 0, 47), at(48, 0, 48)) },
+            TokenData{ token: Token::Value(Value::Number(12_345.67)), source: "12,345.67".into(), span: Span::new(at(49, 0, 49), at(58, 0, 58)) },
+        ];
 
         to_postfix(input);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn numbers_nodes_in_preorder() {
+        let input = "a = 1 & (b = 2 | c = 3) & d = 4";
+        let search = parse(crate::lexer::lex(input).0).unwrap();
+
+        let ComparisonOrSearch::Search(or) = &search.comparisons[1] else { panic!("Expected a search") };
+        let ComparisonOrSearch::Comparison(c) = &or.comparisons[1] else { panic!("Expected a comparison") };
+
+        let nodes: Vec<_> = search.nodes().collect();
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(nodes[0], (NodeId(0), Node::Search(&search)));
+        assert_eq!(nodes[2], (NodeId(2), Node::Search(or)));
+        assert_eq!((or.id, c.id), (NodeId(2), NodeId(4)));
+
+        let table = search.node_table();
+        assert_eq!(table.len(), 6);
+        assert_eq!(table.get(NodeId(4)), Some(Node::Comparison(c)));
+        assert_eq!(table.get(NodeId(6)), None);
+    }
+
+    #[test]
+    fn keeps_node_ids_when_the_tree_is_rearranged() {
+        let search = parse(crate::lexer::lex("a = \"some long text\" & (b = 2 | c = 3) & d = 4").0).unwrap();
+        let optimized = crate::eval::optimize(&search);
+        assert_ne!(optimized.comparisons, search.comparisons);
+
+        // The optimized tree's comparisons are in a different order, but each still has the ID it was parsed with
+        let table = optimized.node_table();
+        for (id, node) in search.nodes() {
+            match node {
+                Node::Comparison(_) => assert_eq!(table.get(id), Some(node), "{:?}", id),
+                Node::Search(search) => assert_eq!(table.get(id).map(|node| node.id()), Some(search.id))
+            }
+        }
+
+        let mut built = Search::new(vec![ComparisonOrSearch::Search(Search::new(Vec::new(), JoinType::Or))], JoinType::And);
+        built.number_nodes();
+        assert_eq!(built.nodes().map(|(id, _)| id).collect::<Vec<_>>(), [NodeId(0), NodeId(1)]);
+    }
+
+    #[test]
+    fn parses_long_chains() {
+        let input = (0..10_000).map(|i| format!("test_{} = {}", i, i)).collect::<Vec<_>>().join(" | ");
+        let search = parse(crate::lexer::lex(&input).0).unwrap();
+
+        assert_eq!(search.join_type, JoinType::Or);
+        assert_eq!(search.comparisons.len(), 10_000);
+    }
+
+    #[test]
+    fn errors_on_missing_join() {
+        let result = parse(crate::lexer::lex("a = 1 b = 2").0);
+
+        assert_eq!(result, Err("Expected `&`, `|` or `^` between comparisons".to_string()));
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::fmt::{Display, Formatter, Write};
+use crate::lexer::{Comparator, JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+
+// Joins longer than this are split so that each operand starts on its own line
+const MAX_WIDTH: usize = 80;
+const INDENT: &str = "    ";
+
+impl Display for Comparator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparator::Equal => "=",
+            Comparator::NotEqual => "!=",
+            Comparator::LessThan => "<",
+            Comparator::GreaterThan => ">",
+            Comparator::LessThanOrEqual => "<=",
+            Comparator::GreaterThanOrEqual => ">="
+        })
+    }
+}
+
+impl Display for JoinType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JoinType::Or => "|",
+            JoinType::And => "&",
+            JoinType::Xor => "^"
+        })
+    }
+}
+
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            // `f64`'s `Display` never uses exponent notation and always prints the shortest string that parses back to
+            // the same number, so the lexer reads it back exactly
+            Value::Number(number) => write!(f, "{}", number),
+            // The lexer has no escape sequences, so a string containing `"` can't be represented in filter text
+            Value::String(string) => write!(f, "\"{}\"", string)
+        }
+    }
+}
+
+impl Display for Comparison<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.name, self.comparator, self.value)
+    }
+}
+
+impl Display for Search<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_search(f, self, Some(0))
+    }
+}
+
+// Writes `search` on a single line if `depth` is `None`, otherwise wraps it once it no longer fits within `MAX_WIDTH`
+// when indented `depth` levels
+fn write_search(f: &mut impl Write, search: &Search, depth: Option<usize>) -> fmt::Result {
+    let Some(depth) = depth else {
+        for (i, comparison) in search.comparisons.iter().enumerate() {
+            if i > 0 {
+                write!(f, " {} ", search.join_type)?;
+            }
+            write_operand(f, comparison, &search.join_type, None)?;
+        }
+        return Ok(());
+    };
+
+    let flat = to_flat_string(search);
+    if search.comparisons.len() < 2 || fits(&flat, depth) {
+        return f.write_str(&flat);
+    }
+
+    for (i, comparison) in search.comparisons.iter().enumerate() {
+        if i > 0 {
+            write!(f, "\n{}{} ", INDENT.repeat(depth), search.join_type)?;
+        }
+        write_operand(f, comparison, &search.join_type, Some(depth))?;
+    }
+    Ok(())
+}
+
+fn write_operand(f: &mut impl Write, operand: &ComparisonOrSearch, parent_join_type: &JoinType, depth: Option<usize>) -> fmt::Result {
+    match operand {
+        ComparisonOrSearch::Comparison(comparison) => write!(f, "{}", comparison),
+
+        // `to_postfix` gives Xor > And > Or precedence, so only a lower-precedence join nested in a higher-precedence
+        // one needs parentheses
+        ComparisonOrSearch::Search(search) if search.join_type < *parent_join_type => {
+            let Some(depth) = depth else {
+                f.write_char('(')?;
+                write_search(f, search, None)?;
+                return f.write_char(')');
+            };
+
+            let flat = to_flat_string(search);
+            // Account for the leading join operator and the surrounding parentheses
+            if fits(&flat, depth + 1) {
+                return write!(f, "({})", flat);
+            }
+            write!(f, "(\n{}", INDENT.repeat(depth + 1))?;
+            write_search(f, search, Some(depth + 1))?;
+            write!(f, "\n{})", INDENT.repeat(depth))
+        },
+
+        ComparisonOrSearch::Search(search) => write_search(f, search, depth.map(|depth| depth + 1))
+    }
+}
+
+fn to_flat_string(search: &Search) -> String {
+    let mut flat = String::new();
+    write_search(&mut flat, search, None).expect("Writing to a String can't fail");
+    flat
+}
+
+fn fits(line: &str, depth: usize) -> bool {
+    depth * INDENT.len() + line.chars().count() <= MAX_WIDTH
+}
+
+
+#[cfg(test)]
+mod printer_tests {
+    use crate::lexer::lex;
+    use crate::parser::parse;
+    use super::*;
+
+    fn comparison<'a>(name: &'a str, comparator: Comparator, value: Value<'a>) -> ComparisonOrSearch<'a> {
+        ComparisonOrSearch::Comparison(Comparison::new(name.into(), comparator, value))
+    }
+
+    fn format(input: &str) -> String {
+        let tokens = lex(input);
+        assert_eq!(tokens.1, None);
+        parse(tokens.0).unwrap().to_string()
+    }
+
+    #[test]
+    fn prints_every_comparator() {
+        let input = Search::new(
+            vec![
+                comparison("a", Comparator::Equal, Value::Number(1.)),
+                comparison("b", Comparator::NotEqual, Value::Number(-2.5)),
+                comparison("c", Comparator::LessThan, Value::Number(0.125)),
+                comparison("d", Comparator::GreaterThan, Value::String("d".into())),
+                comparison("e", Comparator::LessThanOrEqual, Value::String("".into())),
+                comparison("f", Comparator::GreaterThanOrEqual, Value::Number(12_345.67))
+            ],
+            JoinType::And
+        );
+
+        assert_eq!(input.to_string(), "a = 1 & b != -2.5 & c < 0.125 & d > \"d\" & e <= \"\" & f >= 12345.67");
+    }
+
+    #[test]
+    fn prints_single_comparison_without_join() {
+        assert_eq!(format("test=\"test\""), "test = \"test\"");
+    }
+
+    #[test]
+    fn prints_empty_search() {
+        let input = Search::new(Vec::new(), JoinType::And);
+
+        assert_eq!(input.to_string(), "");
+    }
+
+    #[test]
+    fn normalizes_spacing() {
+        assert_eq!(format("test>=1,000|\n  test_2!=\"x y\"   &test_3<.5"), "test >= 1000 | test_2 != \"x y\" & test_3 < 0.5");
+    }
+
+    #[test]
+    fn omits_parentheses_for_higher_precedence_joins() {
+        assert_eq!(format("(a = 1 & b = 2) | (c = 3 ^ d = 4)"), "a = 1 & b = 2 | c = 3 ^ d = 4");
+    }
+
+    #[test]
+    fn keeps_parentheses_for_lower_precedence_joins() {
+        assert_eq!(format("(a = 1 | b = 2) & (c = 3 | d = 4)"), "(a = 1 | b = 2) & (c = 3 | d = 4)");
+        assert_eq!(format("a = 1 ^ (b = 2 & (c = 3 | d = 4))"), "a = 1 ^ (b = 2 & (c = 3 | d = 4))");
+    }
+
+    #[test]
+    fn drops_redundant_parentheses_around_same_join() {
+        assert_eq!(format("((a = 1) & (b = 2 & c = 3))"), "a = 1 & b = 2 & c = 3");
+    }
+
+    #[test]
+    fn wraps_long_joins() {
+        let input = "status = \"open\" & (priority > 2 | assignee = \"me\") & created >= \"2024-01-01\" & reporter != \"bot\"";
+
+        assert_eq!(format(input), "\
+status = \"open\"
+& (priority > 2 | assignee = \"me\")
+& created >= \"2024-01-01\"
+& reporter != \"bot\"");
+    }
+
+    #[test]
+    fn wraps_long_nested_joins() {
+        let input = "status = \"open\" & (priority > 2 | assignee = \"somebody with a long name\" | reporter = \"somebody else\" | watchers > 10)";
+
+        assert_eq!(format(input), "\
+status = \"open\"
+& (
+    priority > 2
+    | assignee = \"somebody with a long name\"
+    | reporter = \"somebody else\"
+    | watchers > 10
+)");
+    }
+
+    #[test]
+    fn indents_wrapped_higher_precedence_joins() {
+        let input = "status = \"open\" & assignee = \"somebody with a long name\" & reporter = \"somebody else\" | watchers > 10";
+
+        assert_eq!(format(input), "\
+status = \"open\"
+    & assignee = \"somebody with a long name\"
+    & reporter = \"somebody else\"
+| watchers > 10");
+    }
+}
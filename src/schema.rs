@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use serde_json::Value as Json;
+use wasm_bindgen::prelude::*;
+use crate::lexer::{Comparator, Value};
+use crate::parser::{Comparison, Node, Search};
+use crate::suggest;
+use crate::types::{FilterError, Fix};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldType {
+    Number,
+    String,
+    // Written as the string `"true"` or `"false"`, since filters have no boolean literals
+    Bool,
+    // Written as an ISO 8601 string, e.g. `"2024-01-31"` or `"2024-01-31T12:00:00Z"`
+    Datetime,
+    // A string that must be one of the given values
+    Enum(Vec<String>)
+}
+
+impl FieldType {
+    // Whether `<`, `>`, `<=` and `>=` mean anything for this type
+    pub fn is_ordered(&self) -> bool {
+        matches!(self, FieldType::Number | FieldType::String | FieldType::Datetime)
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FieldType::Number => "a number",
+            FieldType::String => "a string",
+            FieldType::Bool => "a bool",
+            FieldType::Datetime => "a datetime",
+            FieldType::Enum(_) => "an enum"
+        })
+    }
+}
+
+// The fields a filter may use, their types, and optionally what they mean
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    fields: HashMap<String, FieldType>,
+    descriptions: HashMap<String, String>
+}
+
+impl Schema {
+    pub fn add_field(&mut self, name: &str, field_type: FieldType) {
+        self.fields.insert(name.to_string(), field_type);
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldType> {
+        self.fields.get(name)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &FieldType)> {
+        self.fields.iter().map(|(name, field_type)| (name.as_str(), field_type))
+    }
+
+    pub fn description(&self, name: &str) -> Option<&str> {
+        self.descriptions.get(name).map(String::as_str)
+    }
+}
+
+#[wasm_bindgen]
+impl Schema {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Reads a schema from a JSON object mapping each field to its type: `"number"`, `"string"`, `"bool"`, `"datetime"`,
+    // or an array of an enum's values. A field can also be given as `{"type": ..., "description": "..."}`.
+    pub fn from_json(json: &str) -> Result<Schema, String> {
+        let json: Json = serde_json::from_str(json).map_err(|error| format!("Invalid schema: {}", error))?;
+        let Json::Object(fields) = json else {
+            return Err("Invalid schema: expected an object mapping field names to types".to_string());
+        };
+
+        let mut schema = Schema::new();
+        for (name, mut field_type) in fields {
+            if let Json::Object(field) = &mut field_type {
+                if let Some(Json::String(description)) = field.get("description") {
+                    schema.describe(&name, description);
+                }
+                field_type = field.remove("type").unwrap_or(Json::Null);
+            }
+
+            let field_type = match field_type {
+                Json::String(field_type) if field_type == "number" => FieldType::Number,
+                Json::String(field_type) if field_type == "string" => FieldType::String,
+                Json::String(field_type) if field_type == "bool" => FieldType::Bool,
+                Json::String(field_type) if field_type == "datetime" => FieldType::Datetime,
+                Json::Array(values) => FieldType::Enum(
+                    values.into_iter()
+                        .map(|value| match value {
+                            Json::String(value) => Ok(value),
+                            value => Err(format!("Invalid schema: value `{}` of enum field `{}` isn't a string", value, name))
+                        })
+                        .collect::<Result<_, _>>()?
+                ),
+                field_type => return Err(format!("Invalid schema: unknown type `{}` for field `{}`", field_type, name))
+            };
+            schema.add_field(&name, field_type);
+        }
+        Ok(schema)
+    }
+
+    pub fn add_number(&mut self, name: &str) {
+        self.add_field(name, FieldType::Number);
+    }
+
+    pub fn add_string(&mut self, name: &str) {
+        self.add_field(name, FieldType::String);
+    }
+
+    pub fn add_bool(&mut self, name: &str) {
+        self.add_field(name, FieldType::Bool);
+    }
+
+    pub fn add_datetime(&mut self, name: &str) {
+        self.add_field(name, FieldType::Datetime);
+    }
+
+    pub fn add_enum(&mut self, name: &str, values: Vec<String>) {
+        self.add_field(name, FieldType::Enum(values));
+    }
+
+    // Sets what a field means, for showing to whoever writes the filter
+    pub fn describe(&mut self, name: &str, description: &str) {
+        self.descriptions.insert(name.to_string(), description.to_string());
+    }
+}
+
+// Checks every comparison in `search` against `schema`, returning an error for each unknown field, each value that
+// doesn't fit its field's type, and each ordering comparator used on a field that can't be ordered. Errors point at the
+// offending name, value or comparator, with the whole comparison as their range.
+pub fn check(search: &Search, schema: &Schema) -> Vec<FilterError> {
+    let mut errors = Vec::new();
+    for (_, node) in search.nodes() {
+        if let Node::Comparison(comparison) = node {
+            check_comparison(comparison, schema, &mut errors);
+        }
+    }
+    errors
+}
+
+fn check_comparison(comparison: &Comparison, schema: &Schema, errors: &mut Vec<FilterError>) {
+    let Some(field_type) = schema.field(&comparison.name) else {
+        let suggestions = suggest::closest(&comparison.name, schema.fields().map(|(name, _)| name));
+        errors.push(
+            FilterError::new_with_range(
+                format!("Unknown field `{}`{}", comparison.name, suggest::did_you_mean(&suggestions)),
+                comparison.span,
+                comparison.name_span
+            )
+            .with_fixes(suggestions.iter().map(|name| Fix::replace(comparison.name_span, name)).collect())
+        );
+        return;
+    };
+
+    let mismatch = match (field_type, &comparison.value) {
+        (FieldType::Number, Value::Number(_)) | (FieldType::String, Value::String(_)) => None,
+        (FieldType::Bool, Value::String(value)) if value == "true" || value == "false" => None,
+        (FieldType::Datetime, Value::String(value)) if is_datetime(value) => None,
+        (FieldType::Enum(values), Value::String(value)) if values.iter().any(|allowed| allowed == value) => None,
+
+        (FieldType::Bool, _) => Some(format!("Field `{}` is a bool, so its value must be `\"true\"` or `\"false\"`", comparison.name)),
+        (FieldType::Datetime, _) => Some(format!("Field `{}` is a datetime, but `{}` isn't an ISO 8601 date or time", comparison.name, comparison.value)),
+        (FieldType::Enum(values), _) => Some(format!(
+            "Field `{}` must be one of {}, not `{}`",
+            comparison.name,
+            values.iter().map(|value| format!("`\"{}\"`", value)).collect::<Vec<_>>().join(", "),
+            comparison.value
+        )),
+        (field_type, value) => {
+            let value_type = if let Value::Number(_) = value { FieldType::Number } else { FieldType::String };
+            Some(format!("Field `{}` is {}, but `{}` is {}", comparison.name, field_type, value, value_type))
+        }
+    };
+    if let Some(message) = mismatch {
+        errors.push(FilterError::new_with_range(message, comparison.span, comparison.value_span));
+    }
+
+    if !field_type.is_ordered() && !matches!(comparison.comparator, Comparator::Equal | Comparator::NotEqual) {
+        errors.push(FilterError::new_with_range(
+            format!("Field `{}` is {}, which can't be compared with `{}`; use `=` or `!=`", comparison.name, field_type, comparison.comparator),
+            comparison.span,
+            comparison.comparator_span
+        ));
+    }
+}
+
+// `value` written out in words, e.g. "Wednesday 31 January 2024, 12:00 UTC", if it's an ISO 8601 datetime
+pub fn describe_datetime(value: &str) -> Option<String> {
+    if !is_datetime(value) {
+        return None;
+    }
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None)
+    };
+    let [year, month, day] = split_numbers::<3>(date, '-', [4, 2, 2])?;
+
+    const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+    const MONTHS: [&str; 12] = [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"
+    ];
+    let mut description = format!("{} {} {} {}", WEEKDAYS[weekday(year, month, day) as usize], day, MONTHS[month as usize - 1], year);
+
+    if let Some(time) = time {
+        let (time, offset) = match time.find(['Z', '+', '-']) {
+            Some(i) => time.split_at(i),
+            None => (time, "")
+        };
+        let zone = match offset {
+            "" => "local time".to_string(),
+            "Z" => "UTC".to_string(),
+            offset => format!("UTC{}", offset)
+        };
+        description += &format!(", {} {}", time, zone);
+    }
+    Some(description)
+}
+
+// The day of the week of a date in the Gregorian calendar, with 0 for Sunday
+fn weekday(year: u32, month: u32, day: u32) -> u32 {
+    const MONTH_OFFSETS: [u32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    // January and February count as the end of the year before, so leap days come last. Adding a 400 year cycle, which
+    // is a whole number of weeks, keeps the year 0 from underflowing.
+    let year = if month < 3 { year + 399 } else { year + 400 };
+    (year + year / 4 - year / 100 + year / 400 + MONTH_OFFSETS[month as usize - 1] + day) % 7
+}
+
+// Whether `value` is an ISO 8601 date (`2024-01-31`), optionally followed by a time (`T12:00`, `T12:00:00.5`) and a
+// UTC offset (`Z`, `+02:00`)
+fn is_datetime(value: &str) -> bool {
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None)
+    };
+    is_date(date) && time.is_none_or(is_time)
+}
+
+fn is_date(date: &str) -> bool {
+    let Some([year, month, day]) = split_numbers::<3>(date, '-', [4, 2, 2]) else {
+        return false;
+    };
+
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    };
+    (1..=12).contains(&month) && (1..=days_in_month).contains(&day)
+}
+
+fn is_time(time: &str) -> bool {
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(i) => time.split_at(i),
+        None => (time, "")
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, "0"));
+
+    let valid_time = match split_numbers::<3>(time, ':', [2, 2, 2]).or_else(|| split_numbers::<2>(time, ':', [2, 2]).map(|[h, m]| [h, m, 0])) {
+        // Allow a leap second
+        Some([hour, minute, second]) => hour < 24 && minute < 60 && second <= 60,
+        None => false
+    };
+    let valid_offset = match offset.strip_prefix(['+', '-']) {
+        Some(offset) => matches!(split_numbers::<2>(offset, ':', [2, 2]), Some([hour, minute]) if hour < 24 && minute < 60),
+        None => offset.is_empty() || offset == "Z"
+    };
+
+    valid_time && valid_offset && !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit())
+}
+
+// Splits `text` into `N` numbers separated by `separator`, each exactly as many digits as given
+fn split_numbers<const N: usize>(text: &str, separator: char, digits: [usize; N]) -> Option<[u32; N]> {
+    let mut parts = text.split(separator);
+    let mut numbers = [0; N];
+    for (number, digits) in numbers.iter_mut().zip(digits) {
+        let part = parts.next()?;
+        if part.len() != digits || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *number = part.parse().ok()?;
+    }
+
+    parts.next().is_none().then_some(numbers)
+}
+
+
+#[cfg(test)]
+mod schema_tests {
+    use crate::lexer::lex;
+    use crate::parser::parse;
+    use super::*;
+
+    fn schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_number("age");
+        schema.add_string("name");
+        schema.add_bool("active");
+        schema.add_datetime("created");
+        schema.add_enum("status", vec!["open".to_string(), "closed".to_string()]);
+        schema
+    }
+
+    fn check_filter(input: &str) -> Vec<FilterError> {
+        let tokens = lex(input);
+        assert_eq!(tokens.1, None);
+        check(&parse(tokens.0).unwrap(), &schema())
+    }
+
+    // The live editor checks the filter on every keystroke, so half-typed parentheses mustn't trap the wasm module
+    #[test]
+    fn skips_filters_with_misplaced_parens() {
+        for input in ["(age = 1", "age = 1)", "(age = 1 &)", "age = 1 (name = \"x\")"] {
+            assert_eq!(crate::check_filter(input, &schema()), Vec::new(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn accepts_well_typed_filters() {
+        let errors = check_filter(
+            "age >= 18 & name < \"m\" & active = \"true\" & created > \"2024-02-29T23:59:60.5+05:30\" & (status = \"open\" | status != \"closed\")"
+        );
+
+        assert_eq!(errors, Vec::new());
+    }
+
+    #[test]
+    fn flags_unknown_fields() {
+        let input = "age = 1 | agee = 2";
+        let errors = check_filter(input);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unknown field `agee`; did you mean `age`?");
+        assert_eq!(errors[0].span.slice(input), "agee");
+        assert_eq!(errors[0].range.slice(input), "agee = 2");
+        assert_eq!(errors[0].fixes, [Fix::replace(errors[0].span, "age")]);
+
+        let errors = check_filter("xyz = 1");
+        assert_eq!(errors[0].message, "Unknown field `xyz`");
+        assert_eq!(errors[0].fixes, []);
+    }
+
+    #[test]
+    fn flags_type_mismatches() {
+        let input = "age = \"ten\" & name = 10 & active = \"yes\" & created = \"2023-02-29\" & status = \"pending\"";
+        let errors = check_filter(input);
+
+        let messages: Vec<_> = errors.iter().map(|error| error.message.as_str()).collect();
+        assert_eq!(messages, [
+            "Field `age` is a number, but `\"ten\"` is a string",
+            "Field `name` is a string, but `10` is a number",
+            "Field `active` is a bool, so its value must be `\"true\"` or `\"false\"`",
+            "Field `created` is a datetime, but `\"2023-02-29\"` isn't an ISO 8601 date or time",
+            "Field `status` must be one of `\"open\"`, `\"closed\"`, not `\"pending\"`"
+        ]);
+        assert_eq!(errors[0].span.slice(input), "\"ten\"");
+    }
+
+    #[test]
+    fn flags_ordering_unordered_fields() {
+        let input = "active < \"true\" & status >= \"open\" & created <= \"2024-01-01 12:00\"";
+        let errors = check_filter(input);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "Field `active` is a bool, which can't be compared with `<`; use `=` or `!=`");
+        assert_eq!(errors[0].span.slice(input), "<");
+        assert_eq!(errors[1].span.slice(input), ">=");
+    }
+
+    #[test]
+    fn validates_datetimes() {
+        for valid in ["2024-01-31", "2000-02-29", "2024-01-31T00:00", "2024-01-31T12:34:56", "2024-01-31T12:34:56.789Z", "2024-01-31 12:34-08:00"] {
+            assert!(is_datetime(valid), "{}", valid);
+        }
+        for invalid in ["", "2024", "2024-1-31", "1900-02-29", "2024-04-31", "2024-13-01", "2024-01-31T", "2024-01-31T24:00", "2024-01-31T12:00.", "2024-01-31T12:00+5", "yesterday"] {
+            assert!(!is_datetime(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn reads_schemas_from_json() {
+        let schema = Schema::from_json(r#"{
+            "age": {"type": "number", "description": "In years"},
+            "name": "string",
+            "active": "bool",
+            "created": "datetime",
+            "status": ["open", "closed"]
+        }"#).unwrap();
+
+        assert_eq!(schema.field("age"), Some(&FieldType::Number));
+        assert_eq!(schema.description("age"), Some("In years"));
+        assert_eq!(schema.field("name"), Some(&FieldType::String));
+        assert_eq!(schema.field("active"), Some(&FieldType::Bool));
+        assert_eq!(schema.field("created"), Some(&FieldType::Datetime));
+        assert_eq!(schema.field("status"), Some(&FieldType::Enum(vec!["open".to_string(), "closed".to_string()])));
+
+        assert_eq!(Schema::from_json(r#"{"age": "int"}"#).unwrap_err(), "Invalid schema: unknown type `\"int\"` for field `age`");
+        assert_eq!(Schema::from_json(r#"{"status": [1]}"#).unwrap_err(), "Invalid schema: value `1` of enum field `status` isn't a string");
+        assert!(Schema::from_json("[]").is_err());
+        assert!(Schema::from_json("{").is_err());
+    }
+
+    #[test]
+    fn describes_datetimes() {
+        assert_eq!(describe_datetime("2024-01-31").unwrap(), "Wednesday 31 January 2024");
+        assert_eq!(describe_datetime("2000-02-29T12:00Z").unwrap(), "Tuesday 29 February 2000, 12:00 UTC");
+        assert_eq!(describe_datetime("1970-01-01 00:00:00.5-08:00").unwrap(), "Thursday 1 January 1970, 00:00:00.5 UTC-08:00");
+        assert_eq!(describe_datetime("2024-03-01T09:30").unwrap(), "Friday 1 March 2024, 09:30 local time");
+        assert_eq!(describe_datetime("0000-01-01").unwrap(), "Saturday 1 January 0");
+        assert_eq!(describe_datetime("2024-02-30"), None);
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::lexer::{Comparator, JoinType, Value};
+use crate::parser::{Comparison, ComparisonOrSearch, Search};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dialect {
+    Postgres,
+    Sqlite
+}
+
+impl Dialect {
+    // `index` is 0-indexed, but both dialects number their placeholders from 1
+    fn placeholder(&self, index: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("${}", index + 1),
+            Dialect::Sqlite => format!("?{}", index + 1)
+        }
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> String {
+        match self {
+            // Both follow the SQL standard: wrap in double quotes and double any embedded double quotes
+            Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", identifier.replace('"', "\"\""))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Param {
+    Number(f64),
+    String(String)
+}
+
+impl From<&Value<'_>> for Param {
+    fn from(value: &Value<'_>) -> Self {
+        match value {
+            Value::Number(number) => Param::Number(*number),
+            Value::String(string) => Param::String(string.to_string())
+        }
+    }
+}
+
+// Maps the field names users may write in a filter to the columns they're allowed to query. Only fields in the mapping
+// ever reach the generated SQL, so filter text can never name an arbitrary column.
+#[derive(Clone, Debug, Default)]
+pub struct FieldMapping {
+    columns: HashMap<String, Vec<String>>
+}
+
+impl FieldMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `column` may be qualified as `table.column`; each part is quoted separately
+    pub fn insert(&mut self, field: &str, column: &str) {
+        self.columns.insert(field.to_string(), column.split('.').map(String::from).collect());
+    }
+
+    fn column(&self, field: &str, dialect: Dialect) -> Option<String> {
+        let parts = self.columns.get(field)?;
+        Some(parts.iter().map(|part| dialect.quote_identifier(part)).collect::<Vec<_>>().join("."))
+    }
+}
+
+impl<const N: usize> From<[(&str, &str); N]> for FieldMapping {
+    fn from(fields: [(&str, &str); N]) -> Self {
+        let mut mapping = Self::new();
+        for (field, column) in fields {
+            mapping.insert(field, column);
+        }
+        mapping
+    }
+}
+
+// Translates `search` into the body of a `WHERE` clause. Values are never inlined: each one becomes a placeholder and
+// is returned in `Vec<Param>` in placeholder order, ready to bind.
+//
+// A missing (`NULL`) column makes a comparison `NULL` rather than false. That already behaves like false under `AND`,
+// `OR` and `WHERE`, but not under `<>`, so `Xor` operands are wrapped in `COALESCE(..., FALSE)` before comparing them.
+pub fn to_sql(search: &Search, dialect: Dialect, mapping: &FieldMapping) -> Result<(String, Vec<Param>), String> {
+    let mut sql = String::new();
+    let mut params = Vec::new();
+
+    write_search(&mut sql, &mut params, search, dialect, mapping)?;
+
+    Ok((sql, params))
+}
+
+fn write_search(sql: &mut String, params: &mut Vec<Param>, search: &Search, dialect: Dialect, mapping: &FieldMapping) -> Result<(), String> {
+    if search.comparisons.is_empty() {
+        // An empty `And` holds vacuously, while an empty `Or` or `Xor` has no operand that could hold
+        sql.push_str(if search.join_type == JoinType::And { "TRUE" } else { "FALSE" });
+        return Ok(());
+    }
+    if search.comparisons.len() == 1 {
+        return write_operand(sql, params, &search.comparisons[0], dialect, mapping);
+    }
+
+    match search.join_type {
+        JoinType::And | JoinType::Or => {
+            let separator = if search.join_type == JoinType::And { " AND " } else { " OR " };
+            for (i, comparison) in search.comparisons.iter().enumerate() {
+                if i > 0 {
+                    sql.push_str(separator);
+                }
+                write_operand(sql, params, comparison, dialect, mapping)?;
+            }
+        },
+
+        // `a ^ b ^ c` holds when an odd number of operands hold, which is `((a <> b) <> c)` for booleans
+        JoinType::Xor => {
+            sql.push_str(&"(".repeat(search.comparisons.len() - 1));
+            for (i, comparison) in search.comparisons.iter().enumerate() {
+                if i > 0 {
+                    sql.push_str(" <> ");
+                }
+                sql.push_str("COALESCE(");
+                write_operand(sql, params, comparison, dialect, mapping)?;
+                sql.push_str(", FALSE)");
+                if i > 0 {
+                    sql.push(')');
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_operand(sql: &mut String, params: &mut Vec<Param>, operand: &ComparisonOrSearch, dialect: Dialect, mapping: &FieldMapping) -> Result<(), String> {
+    match operand {
+        ComparisonOrSearch::Comparison(comparison) => write_comparison(sql, params, comparison, dialect, mapping),
+        ComparisonOrSearch::Search(search) => {
+            sql.push('(');
+            write_search(sql, params, search, dialect, mapping)?;
+            sql.push(')');
+            Ok(())
+        }
+    }
+}
+
+fn write_comparison(sql: &mut String, params: &mut Vec<Param>, comparison: &Comparison, dialect: Dialect, mapping: &FieldMapping) -> Result<(), String> {
+    let Some(column) = mapping.column(&comparison.name, dialect) else {
+        return Err(format!("Unknown field `{}`", comparison.name));
+    };
+
+    let operator = match comparison.comparator {
+        Comparator::Equal => "=",
+        Comparator::NotEqual => "<>",
+        Comparator::LessThan => "<",
+        Comparator::GreaterThan => ">",
+        Comparator::LessThanOrEqual => "<=",
+        Comparator::GreaterThanOrEqual => ">="
+    };
+
+    write!(sql, "{} {} {}", column, operator, dialect.placeholder(params.len())).expect("Writing to a String can't fail");
+    params.push(Param::from(&comparison.value));
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod sql_tests {
+    use crate::lexer::lex;
+    use crate::parser::parse;
+    use super::*;
+
+    fn mapping() -> FieldMapping {
+        FieldMapping::from([("status", "status"), ("age", "users.age"), ("name", "full name"), ("quote", "a\"b")])
+    }
+
+    fn translate(input: &str, dialect: Dialect) -> Result<(String, Vec<Param>), String> {
+        let tokens = lex(input);
+        assert_eq!(tokens.1, None);
+        to_sql(&parse(tokens.0).unwrap(), dialect, &mapping())
+    }
+
+    #[test]
+    fn translates_every_comparator() {
+        let result = translate("status = \"a\" & status != \"b\" & age < 1 & age > 2 & age <= 3 & age >= 4", Dialect::Postgres).unwrap();
+
+        assert_eq!(result.0, "\"status\" = $1 AND \"status\" <> $2 AND \"users\".\"age\" < $3 AND \"users\".\"age\" > $4 AND \"users\".\"age\" <= $5 AND \"users\".\"age\" >= $6");
+        assert_eq!(result.1, vec![
+            Param::String("a".to_string()), Param::String("b".to_string()),
+            Param::Number(1.), Param::Number(2.), Param::Number(3.), Param::Number(4.)
+        ]);
+    }
+
+    #[test]
+    fn uses_dialect_placeholders() {
+        let input = "status = \"open\" | age >= 18";
+
+        assert_eq!(translate(input, Dialect::Postgres).unwrap().0, "\"status\" = $1 OR \"users\".\"age\" >= $2");
+        assert_eq!(translate(input, Dialect::Sqlite).unwrap().0, "\"status\" = ?1 OR \"users\".\"age\" >= ?2");
+    }
+
+    #[test]
+    fn parenthesizes_nested_searches() {
+        let result = translate("(status = \"open\" | status = \"new\") & age > 18", Dialect::Sqlite).unwrap();
+
+        assert_eq!(result.0, "(\"status\" = ?1 OR \"status\" = ?2) AND \"users\".\"age\" > ?3");
+    }
+
+    #[test]
+    fn translates_xor_to_parity() {
+        let result = translate("status = \"a\" ^ status = \"b\" ^ (age > 1 & age < 5)", Dialect::Postgres).unwrap();
+
+        assert_eq!(result.0, "((COALESCE(\"status\" = $1, FALSE) <> COALESCE(\"status\" = $2, FALSE)) <> COALESCE((\"users\".\"age\" > $3 AND \"users\".\"age\" < $4), FALSE))");
+        assert_eq!(result.1.len(), 4);
+    }
+
+    #[test]
+    fn quotes_identifiers() {
+        let result = translate("name = \"x\" & quote = \"y\"", Dialect::Postgres).unwrap();
+
+        assert_eq!(result.0, "\"full name\" = $1 AND \"a\"\"b\" = $2");
+    }
+
+    #[test]
+    fn never_inlines_values() {
+        let result = translate("status = \"'; DROP TABLE users; --\"", Dialect::Sqlite).unwrap();
+
+        assert_eq!(result.0, "\"status\" = ?1");
+        assert_eq!(result.1, vec![Param::String("'; DROP TABLE users; --".to_string())]);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let result = translate("status = \"open\" & password = \"x\"", Dialect::Postgres);
+
+        assert_eq!(result, Err("Unknown field `password`".to_string()));
+    }
+
+    #[test]
+    fn translates_empty_searches() {
+        let and = Search::new(Vec::new(), JoinType::And);
+        let or = Search::new(Vec::new(), JoinType::Or);
+
+        assert_eq!(to_sql(&and, Dialect::Postgres, &mapping()).unwrap(), ("TRUE".to_string(), vec![]));
+        assert_eq!(to_sql(&or, Dialect::Postgres, &mapping()).unwrap(), ("FALSE".to_string(), vec![]));
+    }
+}
@@ -0,0 +1,79 @@
+// Finds likely intended spellings of a misspelled name, for "did you mean" hints.
+
+const MAX_SUGGESTIONS: usize = 3;
+
+// The candidates closest to `name`, closest first (ties broken alphabetically). Case is ignored, and a candidate only
+// counts as close if it's within about one edit for every three characters, so unrelated names aren't suggested.
+pub fn closest<'c>(name: &str, candidates: impl IntoIterator<Item = &'c str>) -> Vec<&'c str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut close: Vec<_> = candidates.into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    close.sort();
+    close.into_iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| candidate).collect()
+}
+
+// e.g. "; did you mean `age`?" or "; did you mean `age`, `page` or `stage`?", or nothing if there are no suggestions
+pub fn did_you_mean(suggestions: &[&str]) -> String {
+    let quoted: Vec<_> = suggestions.iter().map(|suggestion| format!("`{}`", suggestion)).collect();
+    match quoted.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!("; did you mean {}?", last),
+        Some((last, rest)) => format!("; did you mean {} or {}?", rest.join(", "), last)
+    }
+}
+
+// The Levenshtein distance between `a` and `b`: the fewest single character insertions, deletions and substitutions
+// that turn one into the other
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+
+    // Distances from the prefix of `a` seen so far to each prefix of `b`
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+
+#[cfg(test)]
+mod suggest_tests {
+    use super::*;
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("age", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("agee", "age"), 1);
+        assert_eq!(edit_distance("naïve", "naive"), 1);
+    }
+
+    #[test]
+    fn suggests_close_candidates() {
+        let fields = ["age", "page", "name", "created_at", "stage"];
+
+        assert_eq!(closest("agee", fields), ["age"]);
+        assert_eq!(closest("pge", fields), ["age", "page"]);
+        assert_eq!(closest("Name", fields), ["name"]);
+        assert_eq!(closest("creatd_at", fields), ["created_at"]);
+        assert_eq!(closest("zzz", fields), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn formats_suggestions() {
+        assert_eq!(did_you_mean(&[]), "");
+        assert_eq!(did_you_mean(&["age"]), "; did you mean `age`?");
+        assert_eq!(did_you_mean(&["age", "page", "stage"]), "; did you mean `age`, `page` or `stage`?");
+    }
+}
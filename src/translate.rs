@@ -0,0 +1,65 @@
+// Shared by the translators for query languages without a boolean xor (`mongo` and `elasticsearch`). They rewrite
+// `a ^ b` as `(a & !b) | (!a & b)`, folding longer chains from the left, so both sides of every `^` appear twice and
+// each extra operand doubles the size of the query. Filters that would need more than `MAX_COMPARISONS` comparisons
+// are refused rather than translated.
+
+use crate::lexer::JoinType;
+use crate::parser::{ComparisonOrSearch, Search};
+
+// The most comparisons a translated query may hold
+pub const MAX_COMPARISONS: usize = 1024;
+
+// Errors if `search` would need more than `MAX_COMPARISONS` comparisons with every `^` written out
+pub fn check_xor_expansion(search: &Search) -> Result<(), String> {
+    if expanded_size(search) > MAX_COMPARISONS {
+        return Err(format!("The filter's `^` chains are too long to translate: it would need over {} comparisons", MAX_COMPARISONS));
+    }
+    Ok(())
+}
+
+// How many comparisons `search` has with every `^` written out, saturating rather than overflowing
+pub fn expanded_size(search: &Search) -> usize {
+    let sizes = search.comparisons.iter().map(|operand| match operand {
+        ComparisonOrSearch::Comparison(_) => 1,
+        ComparisonOrSearch::Search(search) => expanded_size(search)
+    });
+    match search.join_type {
+        JoinType::And | JoinType::Or => sizes.fold(0, usize::saturating_add),
+        JoinType::Xor => sizes.reduce(|left, right| left.saturating_add(right).saturating_mul(2)).unwrap_or(0)
+    }
+}
+
+
+#[cfg(test)]
+mod translate_tests {
+    use super::*;
+
+    fn chain(length: usize) -> String {
+        (0..length).map(|i| format!("a = {}", i)).collect::<Vec<_>>().join(" ^ ")
+    }
+
+    #[test]
+    pub fn counts_comparisons_with_xor_written_out() {
+        for (filter, size) in [
+            ("a = 1 & b = 2 | c = 3".to_string(), 3),
+            ("a = 1 ^ b = 2".to_string(), 4),
+            ("a = 1 ^ b = 2 ^ c = 3".to_string(), 10),
+            ("(a = 1 & b = 2) ^ c = 3".to_string(), 6),
+            (chain(9), 766),
+            (chain(10), 1534),
+            (format!("({} & b = 1) ^ c = 1", chain(8)), 768),
+            (chain(100), usize::MAX)
+        ] {
+            assert_eq!(expanded_size(&crate::try_parse(&filter).unwrap()), size, "{}", filter);
+        }
+    }
+
+    #[test]
+    pub fn refuses_filters_too_big_to_write_out() {
+        assert_eq!(check_xor_expansion(&crate::try_parse(&chain(9)).unwrap()), Ok(()));
+        assert_eq!(
+            check_xor_expansion(&crate::try_parse(&chain(10)).unwrap()),
+            Err("The filter's `^` chains are too long to translate: it would need over 1024 comparisons".to_string())
+        );
+    }
+}
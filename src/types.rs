@@ -1,47 +1,109 @@
 use wasm_bindgen::prelude::wasm_bindgen;
 
+// A position in the filter text, counted in each unit a consumer might index it by: UTF-8 bytes for Rust's `str`,
+// `char`s, and UTF-16 code units for JavaScript strings
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub byte: usize,            // 0-indexed
+    pub char: usize,            // 0-indexed
+    pub utf16: usize,           // 0-indexed
+    pub line: usize,            // 0-indexed
+    pub col: usize              // 0-indexed, in chars
+}
+
+impl Position {
+    // The position just after `c`, if `c` starts at this position
+    pub fn after(self, c: char) -> Self {
+        if c == '\n' {
+            Self { byte: self.byte + 1, char: self.char + 1, utf16: self.utf16 + 1, line: self.line + 1, col: 0 }
+        }
+        else {
+            Self { byte: self.byte + c.len_utf8(), char: self.char + 1, utf16: self.utf16 + c.len_utf16(), line: self.line, col: self.col + 1 }
+        }
+    }
+}
+
+// A range of the filter text. Slicing the input with a token's span gives exactly the token's `source`, in whichever
+// unit the input is indexed by.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: Position,        // Inclusive
+    pub end: Position           // Not inclusive
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    // The text this span covers in `input`, which must be the text it was created from
+    pub fn slice(self, input: &str) -> &str {
+        &input[self.start.byte..self.end.byte]
+    }
+
+    // The span from the start of this span to the end of `other`, which must not come before it
+    pub fn to(self, other: Span) -> Self {
+        Self::new(self.start, other.end)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.start.byte == self.end.byte
+    }
+}
+
+// An edit an editor can offer as a quick fix for an error: replace the text in `span` with `replacement`
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fix {
+    pub title: String,          // e.g. "Replace with `age`"
+    pub span: Span,
+    pub replacement: String
+}
+
+impl Fix {
+    pub fn replace(span: Span, replacement: &str) -> Self {
+        Self { title: format!("Replace with `{}`", replacement), span, replacement: replacement.to_string() }
+    }
+}
+
 #[wasm_bindgen(getter_with_clone)]
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FilterError {
     pub message: String,
 
     // Specific location that caused error
-    pub start: usize,           // Inclusive
-    pub start_line: usize,      // Inclusive
-    pub start_col: usize,       // Inclusive
-    pub end: usize,             // Not inclusive
-    pub end_line: usize,        // Inclusive
-    pub end_col: usize,         // Not inclusive
+    pub span: Span,
 
     // Relevant range being considered when error occurs
-    pub range_start: usize,     // Inclusive
-    pub range_end: usize        // Not inclusive
+    pub range: Span,
+
+    // Edits that would fix the error, best first
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fixes: Vec<Fix>
 }
 
 impl FilterError {
-    pub fn new(message: String, range_start: usize, range_end: usize, start: usize, start_line: usize, start_col: usize, end: usize, end_line: usize, end_col: usize) -> Self {
+    pub fn new(message: String, span: Span) -> Self {
+        Self::new_with_range(message, span, span)
+    }
+
+    pub fn new_with_range(message: String, range: Span, span: Span) -> Self {
         Self {
             message,
-            range_start,
-            range_end,
-            start,
-            start_line,
-            start_col,
-            end,
-            end_line,
-            end_col
+            span,
+            range,
+            fixes: Vec::new()
         }
     }
 
-    pub fn new_oneline_context(message: String, line: usize, range_start: usize, range_end: usize, start: usize, start_col: usize, end: usize, end_col: usize) -> Self {
-        Self::new(message, range_start, range_end, start, line, start_col, end, line, end_col)
+    pub fn with_fixes(mut self, fixes: Vec<Fix>) -> Self {
+        self.fixes = fixes;
+        self
     }
-    
-    pub fn new_oneline(message: String, line: usize, start: usize, start_col: usize, end: usize, end_col: usize) -> Self {
-        Self::new(message, start, end, start, line, start_col, end, line, end_col)
-    }
-
-    pub fn new_onechar(message: String, line: usize, start: usize, start_col: usize) -> Self {
-        Self::new_oneline(message, line, start, start_col, start + 1, start_col + 1)
-    }
-}
\ No newline at end of file
+}
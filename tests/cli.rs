@@ -0,0 +1,129 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Runs `wasm-filter` with `args`, giving it `input` on stdin. Returns its exit code, stdout and stderr.
+fn run(args: &[&str], input: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wasm-filter"))
+        .args(args)
+        // Somewhere for the REPL's history that won't touch the real one
+        .env("HOME", env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // It may exit before reading its input, e.g. on a syntax error, which would break the pipe
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+
+    let output = child.wait_with_output().unwrap();
+    (output.status.code().unwrap(), String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+const NDJSON: &str = r#"{"name": "a", "status": "open", "age": 42}
+{"name": "b", "status": "closed", "age": 50}
+
+{"name": "c", "status": "open", "age": 18}
+{"name": "d", "status": "open"}
+"#;
+
+#[test]
+fn filters_ndjson() {
+    let (code, stdout, _) = run(&[r#"status = "open" & age > 30"#], NDJSON);
+
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "{\"name\": \"a\", \"status\": \"open\", \"age\": 42}\n");
+}
+
+#[test]
+fn counts_and_inverts() {
+    assert_eq!(run(&["--count", r#"status = "open""#], NDJSON), (0, "3\n".to_string(), String::new()));
+    assert_eq!(run(&["-c", "-v", r#"status = "open""#], NDJSON), (0, "1\n".to_string(), String::new()));
+
+    let (_, stdout, _) = run(&["--invert", "age >= 18"], NDJSON);
+    assert_eq!(stdout, "{\"name\": \"d\", \"status\": \"open\"}\n");
+}
+
+#[test]
+fn filters_json() {
+    let input = r#"[{"name": "a", "age": 42}, {"name": "b", "age": 12}, {"name": "c", "age": 31}]"#;
+
+    let (code, stdout, _) = run(&["--format", "json", "age > 30"], input);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "[\n  {\"age\":42,\"name\":\"a\"},\n  {\"age\":31,\"name\":\"c\"}\n]\n");
+
+    let (code, stdout, _) = run(&["--format=json", "age > 99"], input);
+    assert_eq!((code, stdout.as_str()), (1, "[]\n"));
+}
+
+#[test]
+fn filters_csv() {
+    let input = "name,status,age,zip\na,open,42,01234\nb,closed,50,\n\"c, jr\",open,31,99999\n";
+
+    let (code, stdout, _) = run(&["--format", "csv", r#"status = "open" & age > 30"#], input);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "name,status,age,zip\na,open,42,01234\n\"c, jr\",open,31,99999\n");
+
+    // Number-like cells are numbers, and empty cells are missing
+    let (_, stdout, _) = run(&["--format", "csv", "--count", "zip = 1234"], input);
+    assert_eq!(stdout, "1\n");
+    let (_, stdout, _) = run(&["--format", "csv", "--count", "zip != 1234"], input);
+    assert_eq!(stdout, "1\n");
+}
+
+#[test]
+fn exits_with_1_when_nothing_matches() {
+    let (code, stdout, stderr) = run(&["age > 100"], NDJSON);
+
+    assert_eq!((code, stdout.as_str(), stderr.as_str()), (1, "", ""));
+}
+
+#[test]
+fn exits_with_2_and_points_at_syntax_errors() {
+    let (code, stdout, stderr) = run(&["status = \"open\" &\nage <> 30"], NDJSON);
+
+    assert_eq!(code, 2);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "\
+error: `<>` isn't a comparator; did you mean `!=`?
+ --> 2:5
+  |
+2 | age <> 30
+  |     ^^
+  = help: Replace with `!=`
+");
+}
+
+#[test]
+fn exits_with_2_on_bad_arguments_or_input() {
+    let (code, _, stderr) = run(&["--format", "xml", "age > 1"], "");
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with("error: Unknown format `xml`; expected `ndjson`, `json` or `csv`\n\nUsage:"));
+
+    let (code, _, stderr) = run(&[], "");
+    assert_eq!((code, stderr.lines().next()), (2, Some("error: Expected a filter")));
+
+    let (code, _, stderr) = run(&["age > 1"], "{\"age\": 2}\n[1]\n");
+    assert_eq!((code, stderr.as_str()), (2, "error: Line 2 isn't a JSON object\n"));
+}
+
+#[test]
+fn tries_filters_against_a_file() {
+    let path = env::temp_dir().join(format!("wasm-filter-test-{}.csv", std::process::id()));
+    fs::write(&path, "name,age\nAda,36\nAlan,41\n").unwrap();
+
+    let (code, stdout, _) = run(&["--repl", path.to_str().unwrap()], "age > 40\n:explain age > 1 | age < 0\nage <\n:quit\n");
+    assert_eq!(code, 0);
+    assert_eq!(stdout.lines().collect::<Vec<_>>()[1..7], [
+        "1 of 2 records match", "name,age", "Alan,41",
+        "any of:", "    age > 1", "    age < 0"
+    ]);
+    assert!(stdout.contains("error: "));
+
+    let (code, _, stderr) = run(&["--repl", "missing.json"], "");
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with("error: Couldn't read `missing.json`: "));
+
+    fs::remove_file(path).unwrap();
+}
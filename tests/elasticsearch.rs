@@ -0,0 +1,123 @@
+use serde_json::json;
+use wasm_filter::elasticsearch::*;
+use wasm_filter::lexer::*;
+use wasm_filter::parser::*;
+
+fn translate(input: &str, options: &QueryOptions) -> serde_json::Value {
+    let result = lex(input);
+    assert_eq!(result.1, None);
+    to_elasticsearch(&parse(result.0).unwrap(), options).unwrap()
+}
+
+#[test]
+fn translates_equal_to_term() {
+    assert_eq!(translate("status = \"open\"", &QueryOptions::new()), json!({ "term": { "status": "open" } }));
+}
+
+#[test]
+fn translates_not_equal_to_must_not() {
+    assert_eq!(translate("status != \"open\"", &QueryOptions::new()), json!({ "bool": { "must_not": [{ "term": { "status": "open" } }] } }));
+}
+
+#[test]
+fn translates_ordering_comparators_to_range() {
+    let options = QueryOptions::new();
+
+    assert_eq!(translate("age < 30", &options), json!({ "range": { "age": { "lt": 30.0 } } }));
+    assert_eq!(translate("age > -2.5", &options), json!({ "range": { "age": { "gt": -2.5 } } }));
+    assert_eq!(translate("age <= 1,000", &options), json!({ "range": { "age": { "lte": 1000.0 } } }));
+    assert_eq!(translate("created >= \"2024-01-01\"", &options), json!({ "range": { "created": { "gte": "2024-01-01" } } }));
+}
+
+#[test]
+fn uses_match_for_configured_fields() {
+    let mut options = QueryOptions::new();
+    options.set_equality("message", EqualityQuery::Match);
+
+    assert_eq!(translate("message = \"disk full\" & host = \"db-1\"", &options), json!({ "bool": { "must": [
+        { "match": { "message": "disk full" } },
+        { "term": { "host": "db-1" } }
+    ]}}));
+    assert_eq!(translate("message != \"disk full\"", &options), json!({ "bool": { "must_not": [{ "match": { "message": "disk full" } }] } }));
+}
+
+#[test]
+fn uses_configured_default_equality() {
+    let options = QueryOptions { default_equality: EqualityQuery::Match, ..QueryOptions::new() };
+
+    assert_eq!(translate("message = \"disk full\"", &options), json!({ "match": { "message": "disk full" } }));
+}
+
+#[test]
+fn translates_and_to_must_and_must_not() {
+    assert_eq!(translate("status = \"open\" & age > 30 & host != \"db-1\"", &QueryOptions::new()), json!({ "bool": {
+        "must": [
+            { "term": { "status": "open" } },
+            { "range": { "age": { "gt": 30.0 } } }
+        ],
+        "must_not": [
+            { "term": { "host": "db-1" } }
+        ]
+    }}));
+}
+
+#[test]
+fn translates_and_of_only_not_equal() {
+    assert_eq!(translate("a != 1 & b != 2", &QueryOptions::new()), json!({ "bool": { "must_not": [
+        { "term": { "a": 1.0 } },
+        { "term": { "b": 2.0 } }
+    ]}}));
+}
+
+#[test]
+fn translates_or_to_should() {
+    assert_eq!(translate("status = \"open\" | status != \"closed\"", &QueryOptions::new()), json!({ "bool": {
+        "should": [
+            { "term": { "status": "open" } },
+            { "bool": { "must_not": [{ "term": { "status": "closed" } }] } }
+        ],
+        "minimum_should_match": 1
+    }}));
+}
+
+#[test]
+fn translates_xor() {
+    let a = json!({ "term": { "a": 1.0 } });
+    let b = json!({ "term": { "b": 2.0 } });
+
+    assert_eq!(translate("a = 1 ^ b = 2", &QueryOptions::new()), json!({ "bool": {
+        "should": [
+            { "bool": { "must": [a], "must_not": [b] } },
+            { "bool": { "must": [b], "must_not": [a] } }
+        ],
+        "minimum_should_match": 1
+    }}));
+}
+
+#[test]
+fn translates_nested_joins_to_nested_bool_queries() {
+    assert_eq!(translate("level = \"error\" & (host = \"db-1\" | host = \"db-2\")", &QueryOptions::new()), json!({ "bool": { "must": [
+        { "term": { "level": "error" } },
+        { "bool": {
+            "should": [
+                { "term": { "host": "db-1" } },
+                { "term": { "host": "db-2" } }
+            ],
+            "minimum_should_match": 1
+        }}
+    ]}}));
+}
+
+#[test]
+fn translates_empty_searches() {
+    let options = QueryOptions::new();
+
+    assert_eq!(to_elasticsearch(&Search::new(Vec::new(), JoinType::And), &options), Ok(json!({ "match_all": {} })));
+    assert_eq!(to_elasticsearch(&Search::new(Vec::new(), JoinType::Or), &options), Ok(json!({ "match_none": {} })));
+}
+
+#[test]
+fn rejects_xor_chains_too_long_to_translate() {
+    let filter = (0..10).map(|i| format!("a = {}", i)).collect::<Vec<_>>().join(" ^ ");
+    assert!(to_elasticsearch(&parse(lex(&filter).0).unwrap(), &QueryOptions::new()).is_err());
+}
@@ -0,0 +1,60 @@
+use proptest::prelude::*;
+use serde_json::{json, Map, Value as Json};
+use wasm_filter::eval::{matches, optimize};
+use wasm_filter::lexer::*;
+use wasm_filter::parser::*;
+
+// Few names and values, so that comparisons often hit fields in the records and hold about as often as not
+fn value() -> impl Strategy<Value = Value<'static>> {
+    prop_oneof![
+        (0..4).prop_map(|n| Value::Number(n as f64)),
+        "[ab]{0,2}".prop_map(|string| Value::String(string.into()))
+    ]
+}
+
+fn comparison() -> impl Strategy<Value = ComparisonOrSearch<'static>> {
+    let comparator = prop_oneof![
+        Just(Comparator::Equal),
+        Just(Comparator::NotEqual),
+        Just(Comparator::LessThan),
+        Just(Comparator::GreaterThan),
+        Just(Comparator::LessThanOrEqual),
+        Just(Comparator::GreaterThanOrEqual)
+    ];
+    ("[abc]", comparator, value())
+        .prop_map(|(name, comparator, value)| ComparisonOrSearch::Comparison(Comparison::new(name.into(), comparator, value)))
+}
+
+fn search() -> impl Strategy<Value = Search<'static>> {
+    let join_type = prop_oneof![Just(JoinType::Or), Just(JoinType::And), Just(JoinType::Xor)];
+    let operand = comparison().prop_recursive(4, 32, 5, |inner| {
+        (prop_oneof![Just(JoinType::Or), Just(JoinType::And), Just(JoinType::Xor)], prop::collection::vec(inner, 0..5))
+            .prop_map(|(join_type, operands)| ComparisonOrSearch::Search(Search::new(operands, join_type)))
+    });
+    (join_type, prop::collection::vec(operand, 0..5)).prop_map(|(join_type, operands)| Search::new(operands, join_type))
+}
+
+fn record() -> impl Strategy<Value = Map<String, Json>> {
+    let field = prop_oneof![
+        Just(Json::Null),
+        (0..4).prop_map(|n| json!(n)),
+        "[ab]{0,2}".prop_map(|string| json!(string))
+    ];
+    (field.clone(), field.clone(), field).prop_map(|(a, b, c)| {
+        let Json::Object(record) = json!({ "a": a, "b": b, "c": c }) else { unreachable!() };
+        record
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn optimizing_never_changes_which_records_match(search in search(), records in prop::collection::vec(record(), 1..8)) {
+        let optimized = optimize(&search);
+
+        for record in &records {
+            prop_assert_eq!(matches(&optimized, record), matches(&search, record), "optimized to {}", optimized);
+        }
+    }
+}
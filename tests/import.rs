@@ -0,0 +1,127 @@
+use wasm_filter::import::*;
+
+fn lucene(query: &str) -> String {
+    from_lucene(query).unwrap_or_else(|error| panic!("failed to import {:?}: {}", query, error)).to_string()
+}
+
+fn query_string(query: &str) -> String {
+    from_query_string(query).unwrap_or_else(|error| panic!("failed to import {:?}: {}", query, error)).to_string()
+}
+
+#[test]
+fn imports_lucene_terms() {
+    assert_eq!(lucene("status:open"), "status = \"open\"");
+    assert_eq!(lucene("status:\"in progress\""), "status = \"in progress\"");
+    assert_eq!(lucene("priority:2"), "priority = 2");
+    assert_eq!(lucene("priority:\"2\""), "priority = \"2\"");
+    assert_eq!(lucene("delta:-1.5"), "delta = -1.5");
+    assert_eq!(lucene("path:a\\:b"), "path = \"a:b\"");
+}
+
+#[test]
+fn imports_lucene_comparison_shorthands() {
+    assert_eq!(lucene("priority:>2"), "priority > 2");
+    assert_eq!(lucene("priority:>=2"), "priority >= 2");
+    assert_eq!(lucene("priority:<2"), "priority < 2");
+    assert_eq!(lucene("priority:<=2"), "priority <= 2");
+}
+
+#[test]
+fn imports_lucene_ranges() {
+    assert_eq!(lucene("age:[18 TO 65]"), "age >= 18 & age <= 65");
+    assert_eq!(lucene("age:{18 TO 65}"), "age > 18 & age < 65");
+    assert_eq!(lucene("age:[18 TO 65}"), "age >= 18 & age < 65");
+    assert_eq!(lucene("age:[18 TO *]"), "age >= 18");
+    assert_eq!(lucene("created:{* TO \"2024-01-01\"}"), "created < \"2024-01-01\"");
+}
+
+#[test]
+fn imports_lucene_operators() {
+    assert_eq!(lucene("status:open AND priority:>2"), "status = \"open\" & priority > 2");
+    assert_eq!(lucene("status:open && priority:>2"), "status = \"open\" & priority > 2");
+    assert_eq!(lucene("status:open OR status:new"), "status = \"open\" | status = \"new\"");
+    assert_eq!(lucene("status:open || status:new"), "status = \"open\" | status = \"new\"");
+    assert_eq!(lucene("a:1 OR b:2 AND c:3"), "a = 1 | b = 2 & c = 3");
+    assert_eq!(lucene("(a:1 OR b:2) AND c:3"), "(a = 1 | b = 2) & c = 3");
+}
+
+#[test]
+fn imports_lucene_negation() {
+    assert_eq!(lucene("NOT status:closed"), "status != \"closed\"");
+    assert_eq!(lucene("status:open AND NOT assignee:me"), "status = \"open\" & assignee != \"me\"");
+    assert_eq!(lucene("!priority:>2"), "priority <= 2");
+    assert_eq!(lucene("-age:[18 TO 65]"), "age < 18 | age > 65");
+    assert_eq!(lucene("NOT (a:1 AND (b:2 OR c:3))"), "a != 1 | b != 2 & c != 3");
+}
+
+#[test]
+fn imports_lucene_implicit_operators() {
+    assert_eq!(lucene("status:open status:new"), "status = \"open\" | status = \"new\"");
+    assert_eq!(lucene("+status:open priority:high -assignee:me"), "status = \"open\" & assignee != \"me\"");
+    assert_eq!(lucene("status:open -assignee:me"), "assignee != \"me\" & status = \"open\"");
+}
+
+#[test]
+fn does_not_read_field_names_as_keywords() {
+    assert_eq!(lucene("ORDER:1 ANDROID:2"), "ORDER = 1 | ANDROID = 2");
+}
+
+#[test]
+fn rejects_unsupported_lucene_syntax() {
+    assert!(from_lucene("open").is_err());
+    assert!(from_lucene("status:op*").is_err());
+    assert!(from_lucene("status:*").is_err());
+    assert!(from_lucene("status:open~2").is_err());
+    assert!(from_lucene("status:open^2").is_err());
+    assert!(from_lucene("user.name:me").is_err());
+    assert!(from_lucene("(status:open").is_err());
+    assert!(from_lucene("status:open)").is_err());
+    assert!(from_lucene("status:open AND").is_err());
+    assert!(from_lucene("age:[1 5]").is_err());
+    assert!(from_lucene("age:[* TO *]").is_err());
+    assert_eq!(
+        from_lucene(r#"title:"say \"hi\"""#).err(),
+        Some("Value `say \"hi\"` on `title` contains a `\"`, which can't be written in filter syntax".to_string())
+    );
+    assert!(from_lucene(r#"title:say\"hi"#).is_err());
+    assert!(from_lucene(r#"title:[a\" TO b]"#).is_err());
+}
+
+#[test]
+fn imports_query_strings() {
+    assert_eq!(query_string("?status=open&priority_gte=2"), "status = \"open\" & priority >= 2");
+    assert_eq!(query_string("status=open"), "status = \"open\"");
+    assert_eq!(query_string(""), "");
+}
+
+#[test]
+fn imports_query_string_suffixes() {
+    assert_eq!(
+        query_string("a_eq=1&b_ne=2&c_lt=3&d_lte=4&e_gt=5&f_gte=6&g=7"),
+        "a = 1 & b != 2 & c < 3 & d <= 4 & e > 5 & f >= 6 & g = 7"
+    );
+    assert_eq!(query_string("_lt=1"), "_lt = 1");
+}
+
+#[test]
+fn decodes_query_strings() {
+    assert_eq!(query_string("name=Jane+Doe&city=S%C3%A3o%20Paulo&note=a%26b%3Dc"), "name = \"Jane Doe\" & city = \"São Paulo\" & note = \"a&b=c\"");
+}
+
+#[test]
+fn groups_repeated_query_string_parameters() {
+    assert_eq!(query_string("status=open&priority_gt=2&status=new"), "(status = \"open\" | status = \"new\") & priority > 2");
+    assert_eq!(query_string("age_gt=2&age_gt=3"), "age > 2 & age > 3");
+}
+
+#[test]
+fn rejects_invalid_query_strings() {
+    assert!(from_query_string("status").is_err());
+    assert!(from_query_string("status=%2").is_err());
+    assert!(from_query_string("status=%FF").is_err());
+    assert!(from_query_string("user.name=me").is_err());
+    assert_eq!(
+        from_query_string("title=say%20%22hi%22").err(),
+        Some("Value `say \"hi\"` on `title` contains a `\"`, which can't be written in filter syntax".to_string())
+    );
+}
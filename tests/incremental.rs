@@ -0,0 +1,68 @@
+use proptest::prelude::*;
+use wasm_filter::incremental::*;
+
+// Filter-like text that mixes every kind of token with errors, newlines, unterminated strings and characters that are
+// more than one byte or UTF-16 code unit long
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            Just("a"), Just("b_2"), Just("日"), Just("😀"), Just("1"), Just("-"), Just("."), Just(","),
+            Just("="), Just("!"), Just("<"), Just(">"), Just("&"), Just("|"), Just("^"), Just("("), Just(")"),
+            Just("\""), Just(" "), Just("\n"), Just("@"), Just("=>"), Just("==")
+        ],
+        0..24
+    ).prop_map(|pieces| pieces.concat())
+}
+
+// An edit of `before`, as UTF-16 offsets on character boundaries
+fn edit(before: &str) -> impl Strategy<Value = (usize, usize, String)> {
+    let mut boundaries = vec![0];
+    boundaries.extend(before.chars().scan(0, |offset, c| { *offset += c.len_utf16(); Some(*offset) }));
+
+    (prop::sample::select(boundaries.clone()), prop::sample::select(boundaries), text())
+        .prop_map(|(a, b, inserted)| (a.min(b), a.max(b) - a.min(b), inserted))
+}
+
+fn text_and_edits() -> impl Strategy<Value = (String, Vec<(usize, usize, String)>)> {
+    // Each edit is generated against the text before it, so apply them while generating
+    text().prop_flat_map(|text| {
+        let edits = edit(&text).prop_flat_map(move |first| {
+            let mut after = text.clone();
+            apply(&mut after, &first);
+            (Just(text.clone()), Just(first), edit(&after))
+        });
+        edits.prop_map(|(text, first, second)| (text, vec![first, second]))
+    })
+}
+
+fn apply(text: &mut String, (offset, deleted, inserted): &(usize, usize, String)) {
+    let mut utf16: Vec<u16> = text.encode_utf16().collect();
+    utf16.splice(*offset..offset + deleted, inserted.encode_utf16());
+    *text = String::from_utf16(&utf16).unwrap();
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn edits_give_the_same_tokens_as_lexing_from_scratch((text, edits) in text_and_edits()) {
+        let mut lexer = IncrementalLexer::new(&text);
+        let mut expected_text = text.clone();
+
+        for edit in edits {
+            let before = lexer.tokens();
+            let delta = lexer.edit(edit.0, edit.1, &edit.2).unwrap();
+            apply(&mut expected_text, &edit);
+
+            let expected = IncrementalLexer::new(&expected_text);
+            prop_assert_eq!(lexer.text(), expected_text.clone());
+            prop_assert_eq!(lexer.tokens(), expected.tokens());
+            prop_assert_eq!(lexer.errors(), expected.errors());
+
+            // The delta describes the change to the token list
+            prop_assert_eq!(&lexer.tokens()[..delta.start], &before[..delta.start]);
+            prop_assert_eq!(&lexer.tokens()[delta.start..delta.start + delta.tokens.len()], &delta.tokens[..]);
+            prop_assert_eq!(lexer.tokens().len() - delta.tokens.len(), before.len() - delta.deleted);
+        }
+    }
+}
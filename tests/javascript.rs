@@ -0,0 +1,72 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use serde_json::{json, Value as Json};
+use wasm_filter::eval::matches;
+use wasm_filter::javascript::to_javascript;
+use wasm_filter::lexer::*;
+use wasm_filter::parser::*;
+
+const FILTERS: [&str; 12] = [
+    "age = 30",
+    "age != 30",
+    "age < 30 | age >= 40",
+    "age > -1.5 & age <= 1,000",
+    "name = \"bob\" ^ age > 18",
+    "name < \"carol\" & name >= \"Bob\"",
+    "name != \"\"",
+    "a = 1 ^ b = 2 ^ c = 3",
+    "(a = 1 | b = 2) & (c = 3 ^ name = \"x\")",
+    "emoji > \"\u{1F600}\"",
+    "emoji = \"\u{FF5E}\" & quote = \"it's \\\"",
+    "missing != 1 | age != \"30\""
+];
+
+fn records() -> Vec<Json> {
+    vec![
+        json!({ "age": 30, "name": "bob", "a": 1, "b": 2, "c": 3 }),
+        json!({ "age": 45, "name": "Bob", "a": 1, "b": 0, "c": 0 }),
+        json!({ "age": "30", "name": "", "a": 0, "b": 2, "c": 3 }),
+        json!({ "age": -1.5, "name": "x", "b": 2, "c": 3, "emoji": "\u{FF5E}", "quote": "it's \\" }),
+        json!({ "age": null, "name": ["bob"], "emoji": "\u{1F600}" }),
+        json!({})
+    ]
+}
+
+// Runs each generated predicate over every record in Node.js and checks it agrees with the Rust evaluator
+#[test]
+fn generated_javascript_agrees_with_evaluator() {
+    let Ok(mut node) = Command::new("node").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn() else {
+        eprintln!("Skipping: `node` isn't installed");
+        return;
+    };
+
+    // Numbers too long for an `f64` are infinite
+    let huge = format!("1{}", "0".repeat(400));
+    let filters: Vec<String> = FILTERS.iter().map(|filter| filter.to_string())
+        .chain([format!("age < {}", huge), format!("age > -{} & age != {}", huge, huge)])
+        .collect();
+
+    let records = records();
+    let searches: Vec<Search> = filters.iter().map(|filter| {
+        let tokens = lex(filter);
+        assert_eq!(tokens.1, None);
+        parse(tokens.0).unwrap()
+    }).collect();
+    let predicates: Vec<String> = searches.iter().map(to_javascript).collect();
+
+    let script = format!(
+        "const predicates = [{}];\nconst records = {};\nconsole.log(JSON.stringify(predicates.map(p => records.map(r => p(r)))));",
+        predicates.join(",\n"),
+        Json::from(records.clone())
+    );
+    node.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+    let output = node.wait_with_output().unwrap();
+    assert!(output.status.success(), "node failed: {}", String::from_utf8_lossy(&output.stdout));
+
+    let results: Vec<Vec<bool>> = serde_json::from_slice(&output.stdout).unwrap();
+    for (i, search) in searches.iter().enumerate() {
+        for (j, record) in records.iter().enumerate() {
+            assert_eq!(results[i][j], matches(search, record.as_object().unwrap()), "{} on {}", filters[i], record);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use serde_json::{json, Value};
+
+// Drives `filter-lsp` over its stdin and stdout the way an editor would
+struct Client {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+    // Notifications received while waiting for a response, oldest first
+    notifications: Vec<Value>
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_filter-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Keep the server's logging out of the test output
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = server.stdin.take().unwrap();
+        let stdout = BufReader::new(server.stdout.take().unwrap());
+
+        Self { server, stdin, stdout, next_id: 0, notifications: Vec::new() }
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            match header.trim_end().split_once(": ") {
+                Some(("Content-Length", value)) => length = value.parse().unwrap(),
+                None if header.trim_end().is_empty() => break,
+                _ => {}
+            }
+        }
+
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    // Sends a request and returns its result, keeping any notifications that arrive first
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}));
+
+        loop {
+            let mut message = self.receive();
+            if message["id"] == id {
+                assert_eq!(message["error"], Value::Null, "{} failed", method);
+                return message["result"].take();
+            }
+            self.notifications.push(message);
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({"jsonrpc": "2.0", "method": method, "params": params}));
+    }
+
+    fn next_notification(&mut self) -> Value {
+        if self.notifications.is_empty() {
+            self.receive()
+        }
+        else {
+            self.notifications.remove(0)
+        }
+    }
+
+    fn initialize(&mut self, params: Value) -> Value {
+        let result = self.request("initialize", params);
+        self.notify("initialized", json!({}));
+        result
+    }
+
+    // Replaces the text of the document `file:///filters/test.filter`, and returns the diagnostics published for it
+    fn change(&mut self, version: u32, text: &str) -> Value {
+        if version == 0 {
+            self.notify("textDocument/didOpen", json!({
+                "textDocument": {"uri": URI, "languageId": "filter", "version": 0, "text": text}
+            }));
+        }
+        else {
+            self.notify("textDocument/didChange", json!({
+                "textDocument": {"uri": URI, "version": version},
+                "contentChanges": [{"text": text}]
+            }));
+        }
+
+        let mut notification = self.next_notification();
+        assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+        assert_eq!(notification["params"]["uri"], URI);
+        notification["params"]["diagnostics"].take()
+    }
+
+    fn shut_down(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.server.wait().unwrap().success());
+    }
+}
+
+const URI: &str = "file:///filters/test.filter";
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+    json!({"start": {"line": start.0, "character": start.1}, "end": {"line": end.0, "character": end.1}})
+}
+
+fn at(line: u32, character: u32) -> Value {
+    json!({"textDocument": {"uri": URI}, "position": {"line": line, "character": character}})
+}
+
+#[test]
+fn serves_a_filter_file() {
+    let mut client = Client::start();
+    let result = client.initialize(json!({
+        "capabilities": {},
+        "initializationOptions": {
+            "schema": {"age": {"type": "number", "description": "In years"}, "status": ["open", "closed"]}
+        }
+    }));
+    let capabilities = &result["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert_eq!(capabilities["documentFormattingProvider"], true);
+    assert_eq!(capabilities["semanticTokensProvider"]["legend"]["tokenTypes"], json!(["property", "operator", "string", "number", "keyword"]));
+
+    // Diagnostics for type, lexing and parse errors, positioned by line and UTF-16 code unit
+    let diagnostics = client.change(0, "age > \"x\" &\n  status = \"😀\"");
+    let messages: Vec<_> = diagnostics.as_array().unwrap().iter().map(|diagnostic| diagnostic["message"].as_str().unwrap()).collect();
+    assert_eq!(messages, [
+        "Field `age` is a number, but `\"x\"` is a string",
+        "Field `status` must be one of `\"open\"`, `\"closed\"`, not `\"😀\"`"
+    ]);
+    assert_eq!(diagnostics[0]["range"], range((0, 6), (0, 9)));
+    assert_eq!(diagnostics[1]["range"], range((1, 11), (1, 15)));
+    assert_eq!(diagnostics[1]["severity"], 1);
+
+    let diagnostics = client.change(1, "age <> 1");
+    assert_eq!(diagnostics[0]["message"], "`<>` isn't a comparator; did you mean `!=`?");
+    assert_eq!(diagnostics[0]["range"], range((0, 4), (0, 6)));
+
+    let diagnostics = client.change(2, "age = 1 age = 2");
+    assert_eq!(diagnostics[0]["message"], "Expected `&`, `|` or `^` between comparisons");
+    assert_eq!(diagnostics[0]["range"], range((0, 0), (0, 15)));
+
+    let diagnostics = client.change(3, "(age = 1 |\nage = 2");
+    assert_eq!(diagnostics[0]["message"], "`(` is never closed");
+    assert_eq!(diagnostics[0]["range"], range((0, 0), (0, 1)));
+
+    let diagnostics = client.change(4, "age>1&(status=\"open\"|age<=2,000)");
+    assert_eq!(diagnostics, json!([]));
+
+    // Hover
+    let hover = client.request("textDocument/hover", at(0, 1));
+    assert_eq!(hover["contents"]["value"], "age: number\n\nIn years");
+    assert_eq!(hover["range"], range((0, 0), (0, 3)));
+    assert_eq!(client.request("textDocument/hover", at(0, 30))["contents"]["value"], "2000");
+    assert_eq!(client.request("textDocument/hover", at(0, 6)), Value::Null);
+
+    // Semantic tokens, each as [delta line, delta start, length, type, modifiers]
+    let tokens = client.request("textDocument/semanticTokens/full", json!({"textDocument": {"uri": URI}}));
+    let data: Vec<_> = tokens["data"].as_array().unwrap().chunks(5).map(|token| json!(token)).collect();
+    assert_eq!(data[..4], [json!([0, 0, 3, 0, 0]), json!([0, 3, 1, 1, 0]), json!([0, 1, 1, 3, 0]), json!([0, 1, 1, 4, 0])]);
+    assert_eq!(data.len(), 13);
+
+    // Formatting
+    let edits = client.request("textDocument/formatting", json!({
+        "textDocument": {"uri": URI},
+        "options": {"tabSize": 4, "insertSpaces": true}
+    }));
+    assert_eq!(edits, json!([{"range": range((0, 0), (0, 32)), "newText": "age > 1 & (status = \"open\" | age <= 2000)\n"}]));
+
+    // Completion replaces what's been typed of the token at the cursor
+    client.change(5, "age > 1 &\nst");
+    let completions = client.request("textDocument/completion", at(1, 2));
+    assert_eq!(completions[0]["label"], "status");
+    assert_eq!(completions[0]["kind"], 5);
+    assert_eq!(completions[0]["textEdit"], json!({"range": range((1, 0), (1, 2)), "newText": "status"}));
+
+    let completions = client.request("textDocument/completion", at(0, 4));
+    let labels: Vec<_> = completions.as_array().unwrap().iter().map(|completion| completion["label"].as_str().unwrap()).collect();
+    assert_eq!(labels, ["=", "!=", "<", ">", "<=", ">="]);
+
+    client.shut_down();
+}
+
+#[test]
+fn reads_the_schema_from_the_workspace() {
+    // A space in the path is percent-encoded in the URI
+    let root = std::env::temp_dir().join(format!("filter-lsp test {}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("filter-schema.json"), r#"{"age": "number"}"#).unwrap();
+
+    let mut client = Client::start();
+    client.initialize(json!({"capabilities": {}, "rootUri": format!("file://{}", root.display()).replace(' ', "%20")}));
+    let diagnostics = client.change(0, "agee = 1");
+    assert_eq!(diagnostics[0]["message"], "Unknown field `agee`; did you mean `age`?");
+    client.shut_down();
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn only_checks_syntax_with_an_invalid_schema() {
+    let mut client = Client::start();
+    client.initialize(json!({"capabilities": {}, "initializationOptions": {"schema": ["age"]}}));
+
+    let message = client.next_notification();
+    assert_eq!(message["method"], "window/showMessage");
+    assert_eq!(message["params"]["type"], 1);
+    assert_eq!(
+        message["params"]["message"],
+        "Only checking syntax, since the filter schema couldn't be loaded: Invalid schema: expected an object mapping field names to types"
+    );
+
+    assert_eq!(client.change(0, "anything = 1"), json!([]));
+    assert_eq!(client.change(1, "anything = 1)")[0]["message"], "`)` has no matching `(`");
+    client.shut_down();
+}
+
+#[test]
+fn only_checks_syntax_without_a_schema() {
+    let mut client = Client::start();
+    client.initialize(json!({"capabilities": {}}));
+
+    assert_eq!(client.change(0, "anything = 1"), json!([]));
+    assert_eq!(client.change(1, "anything = 1)")[0]["message"], "`)` has no matching `(`");
+    client.shut_down();
+}
+
+#[test]
+fn rejects_unknown_requests() {
+    let mut client = Client::start();
+    client.initialize(json!({"capabilities": {}}));
+
+    client.send(json!({"jsonrpc": "2.0", "id": 1, "method": "textDocument/rename", "params": {}}));
+    let response = client.receive();
+    assert_eq!(response["error"]["code"], -32601);
+
+    client.shut_down();
+}
+
+#[test]
+fn ignores_malformed_notifications() {
+    let mut client = Client::start();
+    client.initialize(json!({"capabilities": {}}));
+
+    for method in ["textDocument/didOpen", "textDocument/didChange", "textDocument/didClose"] {
+        client.notify(method, json!({"textDocument": {}}));
+    }
+    assert_eq!(client.change(0, "anything = 1)")[0]["message"], "`)` has no matching `(`");
+    client.shut_down();
+}
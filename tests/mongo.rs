@@ -0,0 +1,141 @@
+use serde_json::json;
+use wasm_filter::lexer::*;
+use wasm_filter::mongo::*;
+use wasm_filter::parser::*;
+
+fn translate(input: &str) -> serde_json::Value {
+    let result = lex(input);
+    assert_eq!(result.1, None);
+    to_mongo(&parse(result.0).unwrap()).unwrap()
+}
+
+#[test]
+fn translates_equal() {
+    assert_eq!(translate("status = \"open\""), json!({ "status": { "$eq": "open" } }));
+}
+
+#[test]
+fn translates_not_equal() {
+    assert_eq!(translate("status != \"open\""), json!({ "status": { "$ne": "open" } }));
+}
+
+#[test]
+fn translates_less_than() {
+    assert_eq!(translate("age < 30"), json!({ "age": { "$lt": 30.0 } }));
+}
+
+#[test]
+fn translates_greater_than() {
+    assert_eq!(translate("age > -2.5"), json!({ "age": { "$gt": -2.5 } }));
+}
+
+#[test]
+fn translates_less_than_or_equal() {
+    assert_eq!(translate("age <= 1,000"), json!({ "age": { "$lte": 1000.0 } }));
+}
+
+#[test]
+fn translates_greater_than_or_equal() {
+    assert_eq!(translate("name >= \"m\""), json!({ "name": { "$gte": "m" } }));
+}
+
+#[test]
+fn translates_and() {
+    assert_eq!(translate("status = \"open\" & age > 30 & age < 40"), json!({ "$and": [
+        { "status": { "$eq": "open" } },
+        { "age": { "$gt": 30.0 } },
+        { "age": { "$lt": 40.0 } }
+    ]}));
+}
+
+#[test]
+fn translates_or() {
+    assert_eq!(translate("status = \"open\" | status = \"new\""), json!({ "$or": [
+        { "status": { "$eq": "open" } },
+        { "status": { "$eq": "new" } }
+    ]}));
+}
+
+#[test]
+fn translates_xor() {
+    let a = json!({ "a": { "$eq": 1.0 } });
+    let b = json!({ "b": { "$eq": 2.0 } });
+
+    assert_eq!(translate("a = 1 ^ b = 2"), json!({ "$or": [
+        { "$and": [a, { "$nor": [b] }] },
+        { "$and": [{ "$nor": [a] }, b] }
+    ]}));
+}
+
+#[test]
+fn folds_xor_chains_from_the_left() {
+    let a = json!({ "a": { "$eq": 1.0 } });
+    let b = json!({ "b": { "$eq": 2.0 } });
+    let c = json!({ "c": { "$eq": 3.0 } });
+    let a_xor_b = json!({ "$or": [
+        { "$and": [a, { "$nor": [b] }] },
+        { "$and": [{ "$nor": [a] }, b] }
+    ]});
+
+    assert_eq!(translate("a = 1 ^ b = 2 ^ c = 3"), json!({ "$or": [
+        { "$and": [a_xor_b, { "$nor": [c] }] },
+        { "$and": [{ "$nor": [a_xor_b] }, c] }
+    ]}));
+}
+
+#[test]
+fn translates_and_nested_in_or() {
+    assert_eq!(translate("a = 1 & b = 2 | c = 3"), json!({ "$or": [
+        { "$and": [{ "a": { "$eq": 1.0 } }, { "b": { "$eq": 2.0 } }] },
+        { "c": { "$eq": 3.0 } }
+    ]}));
+}
+
+#[test]
+fn translates_or_nested_in_and() {
+    assert_eq!(translate("(a = 1 | b = 2) & (c = 3 | d = 4)"), json!({ "$and": [
+        { "$or": [{ "a": { "$eq": 1.0 } }, { "b": { "$eq": 2.0 } }] },
+        { "$or": [{ "c": { "$eq": 3.0 } }, { "d": { "$eq": 4.0 } }] }
+    ]}));
+}
+
+#[test]
+fn translates_joins_nested_in_xor() {
+    let a_and_b = json!({ "$and": [{ "a": { "$eq": 1.0 } }, { "b": { "$eq": 2.0 } }] });
+    let c_or_d = json!({ "$or": [{ "c": { "$eq": 3.0 } }, { "d": { "$eq": 4.0 } }] });
+
+    assert_eq!(translate("(a = 1 & b = 2) ^ (c = 3 | d = 4)"), json!({ "$or": [
+        { "$and": [a_and_b, { "$nor": [c_or_d] }] },
+        { "$and": [{ "$nor": [a_and_b] }, c_or_d] }
+    ]}));
+}
+
+#[test]
+fn translates_deeply_nested_joins() {
+    assert_eq!(translate("a = 1 | b = 2 & (c = 3 | d = 4 & (e = 5 | f = 6))"), json!({ "$or": [
+        { "a": { "$eq": 1.0 } },
+        { "$and": [
+            { "b": { "$eq": 2.0 } },
+            { "$or": [
+                { "c": { "$eq": 3.0 } },
+                { "$and": [
+                    { "d": { "$eq": 4.0 } },
+                    { "$or": [{ "e": { "$eq": 5.0 } }, { "f": { "$eq": 6.0 } }] }
+                ]}
+            ]}
+        ]}
+    ]}));
+}
+
+#[test]
+fn translates_empty_searches() {
+    assert_eq!(to_mongo(&Search::new(Vec::new(), JoinType::And)), Ok(json!({})));
+    assert_eq!(to_mongo(&Search::new(Vec::new(), JoinType::Or)), Ok(json!({ "$nor": [{}] })));
+    assert_eq!(to_mongo(&Search::new(Vec::new(), JoinType::Xor)), Ok(json!({ "$nor": [{}] })));
+}
+
+#[test]
+fn rejects_xor_chains_too_long_to_translate() {
+    let filter = (0..10).map(|i| format!("a = {}", i)).collect::<Vec<_>>().join(" ^ ");
+    assert!(to_mongo(&parse(lex(&filter).0).unwrap()).is_err());
+}
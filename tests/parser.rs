@@ -1,4 +1,3 @@
-use std::collections::LinkedList;
 use wasm_filter::lexer::*;
 use wasm_filter::parser::*;
 
@@ -6,27 +5,27 @@ use wasm_filter::parser::*;
 fn parses_with_balanced_joins() {
     let input = "test = \"test\" & test_2 = \"test_2\" | test_3 = \"test_3\" & test_4 = \"test_4\"".to_string();
     
-    let expected_parse = Search {
-        comparisons: LinkedList::from([
-            ComparisonOrSearch::Search(Search {
-                comparisons: LinkedList::from([
-                    ComparisonOrSearch::Comparison(Comparison{ name: "test".to_string(), comparator: Comparator::Equal, value: Value::String("test".to_string()) }),
-                    ComparisonOrSearch::Comparison(Comparison{ name: "test_2".to_string(), comparator: Comparator::Equal, value: Value::String("test_2".to_string()) })
-                ]),
-                join_type: JoinType::And
-            }),
-            ComparisonOrSearch::Search(Search {
-                comparisons: LinkedList::from([
-                    ComparisonOrSearch::Comparison(Comparison{ name: "test_3".to_string(), comparator: Comparator::Equal, value: Value::String("test_3".to_string()) }),
-                    ComparisonOrSearch::Comparison(Comparison{ name: "test_4".to_string(), comparator: Comparator::Equal, value: Value::String("test_4".to_string()) })
-                ]),
-                join_type: JoinType::And
-            })
-        ]),
-        join_type: JoinType::Or
-    };
+    let expected_parse = Search::new(
+        vec![
+            ComparisonOrSearch::Search(Search::new(
+                vec![
+                    ComparisonOrSearch::Comparison(Comparison::new("test".into(), Comparator::Equal, Value::String("test".into()))),
+                    ComparisonOrSearch::Comparison(Comparison::new("test_2".into(), Comparator::Equal, Value::String("test_2".into())))
+                ],
+                JoinType::And
+            )),
+            ComparisonOrSearch::Search(Search::new(
+                vec![
+                    ComparisonOrSearch::Comparison(Comparison::new("test_3".into(), Comparator::Equal, Value::String("test_3".into()))),
+                    ComparisonOrSearch::Comparison(Comparison::new("test_4".into(), Comparator::Equal, Value::String("test_4".into())))
+                ],
+                JoinType::And
+            ))
+        ],
+        JoinType::Or
+    );
     
-    let result = lex(&mut input.chars().peekable(), 0, 0, 0);
+    let result = lex(&input);
     let result = parse(result.0).unwrap();
     assert_eq!(result, expected_parse);
 }
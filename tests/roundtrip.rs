@@ -0,0 +1,101 @@
+use proptest::prelude::*;
+use wasm_filter::lexer::*;
+use wasm_filter::parser::*;
+
+fn comparator() -> impl Strategy<Value = Comparator> {
+    prop_oneof![
+        Just(Comparator::Equal),
+        Just(Comparator::NotEqual),
+        Just(Comparator::LessThan),
+        Just(Comparator::GreaterThan),
+        Just(Comparator::LessThanOrEqual),
+        Just(Comparator::GreaterThanOrEqual)
+    ]
+}
+
+fn join_type() -> impl Strategy<Value = JoinType> {
+    prop_oneof![Just(JoinType::Or), Just(JoinType::And), Just(JoinType::Xor)]
+}
+
+fn value() -> impl Strategy<Value = Value<'static>> {
+    prop_oneof![
+        // Small integers and simple fractions, which are what people actually type
+        (-1_000_000i64..1_000_000).prop_map(|n| Value::Number(n as f64)),
+        (-1_000_000i64..1_000_000, 1u32..5).prop_map(|(n, places)| Value::Number(n as f64 / 10f64.powi(places as i32))),
+        // Anything finite, including subnormals and signed zero
+        (prop::num::f64::NORMAL | prop::num::f64::SUBNORMAL | prop::num::f64::ZERO).prop_map(Value::Number),
+        // Strings can hold anything except `"`, since the lexer has no escape sequences
+        "[^\"]*".prop_map(|string| Value::String(string.into())),
+        "[ -!#-~]*".prop_map(|string| Value::String(string.into()))
+    ]
+}
+
+fn comparison() -> impl Strategy<Value = ComparisonOrSearch<'static>> {
+    ("[a-zA-Z_][a-zA-Z0-9_éß日]{0,12}", comparator(), value())
+        .prop_map(|(name, comparator, value)| ComparisonOrSearch::Comparison(Comparison::new(name.into(), comparator, value)))
+}
+
+// Generates trees in the shape `parse` produces: every join has at least two operands and a join is never nested
+// directly in a join of the same type, since `merge_subtree` flattens those
+fn comparison_or_search() -> impl Strategy<Value = ComparisonOrSearch<'static>> {
+    comparison().prop_recursive(4, 48, 5, |inner| {
+        (join_type(), prop::collection::vec(inner, 2..5)).prop_map(|(join_type, operands)| {
+            let mut comparisons = Vec::new();
+            for operand in operands {
+                match operand {
+                    ComparisonOrSearch::Search(mut search) if search.join_type == join_type => comparisons.append(&mut search.comparisons),
+                    operand => comparisons.push(operand)
+                }
+            }
+            ComparisonOrSearch::Search(Search::new(comparisons, join_type))
+        })
+    })
+}
+
+fn search() -> impl Strategy<Value = Search<'static>> {
+    comparison_or_search().prop_map(|comparison_or_search| match comparison_or_search {
+        ComparisonOrSearch::Search(search) => search,
+        comparison => Search::new(vec![comparison], JoinType::And)
+    })
+}
+
+fn reparse(text: &str) -> Search<'_> {
+    let tokens = lex(text);
+    assert_eq!(tokens.1, None, "failed to lex {:?}", text);
+    parse(tokens.0).unwrap_or_else(|error| panic!("failed to parse {:?}: {}", text, error))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn parsing_printed_search_yields_same_search(search in search()) {
+        let printed = search.to_string();
+
+        prop_assert_eq!(reparse(&printed), search, "printed as {:?}", printed);
+    }
+
+    #[test]
+    fn printing_is_idempotent(search in search()) {
+        let printed = search.to_string();
+
+        prop_assert_eq!(reparse(&printed).to_string(), printed);
+    }
+}
+
+#[test]
+fn round_trips_precedence_edge_cases() {
+    let inputs = [
+        "a = 1 | b = 2 & c = 3 ^ d = 4",
+        "(a = 1 | b = 2) & (c = 3 | d = 4)",
+        "(a = 1 & b = 2) ^ c = 3",
+        "a = 1 ^ (b = 2 | c = 3) ^ d = 4",
+        "((a = 1 | b = 2) & c = 3 | d = 4) ^ e = 5",
+        "a = -0.5 & b != -1,000.25 & c = \"( | & ^ ) = != \n\t日本\""
+    ];
+
+    for input in inputs {
+        let search = reparse(input);
+        assert_eq!(reparse(&search.to_string()), search, "{:?} printed as {:?}", input, search.to_string());
+    }
+}
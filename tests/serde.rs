@@ -0,0 +1,90 @@
+#![cfg(feature = "serde")]
+
+use serde_json::json;
+use wasm_filter::lexer::*;
+use wasm_filter::parser::*;
+use wasm_filter::types::FilterError;
+
+#[test]
+fn serializes_search_to_documented_schema() {
+    let input = "age > 30 & (status = \"open\" | status = \"new\")".to_string();
+
+    let expected = json!({
+        "comparisons": [
+            { "type": "comparison", "name": "age", "comparator": "greater_than", "value": 30.0 },
+            {
+                "type": "search",
+                "comparisons": [
+                    { "type": "comparison", "name": "status", "comparator": "equal", "value": "open" },
+                    { "type": "comparison", "name": "status", "comparator": "equal", "value": "new" }
+                ],
+                "join_type": "or"
+            }
+        ],
+        "join_type": "and"
+    });
+
+    let result = lex(&input);
+    let result = parse(result.0).unwrap();
+    assert_eq!(serde_json::to_value(&result).unwrap(), expected);
+}
+
+#[test]
+fn deserializes_search_from_documented_schema() {
+    let input = json!({
+        "comparisons": [
+            { "type": "comparison", "name": "test", "comparator": "not_equal", "value": "test" },
+            { "type": "comparison", "name": "test_2", "comparator": "less_than_or_equal", "value": 12 }
+        ],
+        "join_type": "xor"
+    });
+
+    let expected = Search::new(
+        vec![
+            ComparisonOrSearch::Comparison(Comparison::new("test".into(), Comparator::NotEqual, Value::String("test".into()))),
+            ComparisonOrSearch::Comparison(Comparison::new("test_2".into(), Comparator::LessThanOrEqual, Value::Number(12.)))
+        ],
+        JoinType::Xor
+    );
+
+    assert_eq!(serde_json::from_value::<Search>(input).unwrap(), expected);
+}
+
+#[test]
+fn serializes_tokens_to_documented_schema() {
+    let input = "test >= 1,000 | (".to_string();
+
+    let expected = json!([
+        { "token": { "type": "name", "value": "test" }, "source": "test", "span": { "start": { "byte": 0, "char": 0, "utf16": 0, "line": 0, "col": 0 }, "end": { "byte": 4, "char": 4, "utf16": 4, "line": 0, "col": 4 } } },
+        { "token": { "type": "comparator", "value": "greater_than_or_equal" }, "source": ">=", "span": { "start": { "byte": 5, "char": 5, "utf16": 5, "line": 0, "col": 5 }, "end": { "byte": 7, "char": 7, "utf16": 7, "line": 0, "col": 7 } } },
+        { "token": { "type": "value", "value": 1000.0 }, "source": "1,000", "span": { "start": { "byte": 8, "char": 8, "utf16": 8, "line": 0, "col": 8 }, "end": { "byte": 13, "char": 13, "utf16": 13, "line": 0, "col": 13 } } },
+        { "token": { "type": "join_type", "value": "or" }, "source": "|", "span": { "start": { "byte": 14, "char": 14, "utf16": 14, "line": 0, "col": 14 }, "end": { "byte": 15, "char": 15, "utf16": 15, "line": 0, "col": 15 } } },
+        { "token": { "type": "open_paren" }, "source": "(", "span": { "start": { "byte": 16, "char": 16, "utf16": 16, "line": 0, "col": 16 }, "end": { "byte": 17, "char": 17, "utf16": 17, "line": 0, "col": 17 } } }
+    ]);
+
+    let result = lex(&input);
+    assert_eq!(result.1, None);
+    assert_eq!(serde_json::to_value(&result.0).unwrap(), expected);
+}
+
+#[test]
+fn round_trips_tokens() {
+    let input = "test = \"a\nb\" ^ test_2 != -2.5".to_string();
+
+    let result = lex(&input);
+    let json = serde_json::to_string(&result.0).unwrap();
+
+    assert_eq!(serde_json::from_str::<Vec<TokenData>>(&json).unwrap(), result.0);
+}
+
+#[test]
+fn round_trips_filter_errors() {
+    let input = "test = 2.3 |\n test_2 @ 5".to_string();
+
+    let error = lex(&input).1.unwrap();
+    let json = serde_json::to_value(&error).unwrap();
+
+    assert_eq!(json["message"], "Unexpected character '@'");
+    assert_eq!(json["span"]["start"]["line"], 1);
+    assert_eq!(serde_json::from_value::<FilterError>(json).unwrap(), error);
+}
@@ -11,6 +11,36 @@ document?.getElementById('filter-input')
 document.getElementById('filter-input')
     ?.addEventListener('input', handleFilterInput);
 
+document.getElementById('filter-input')
+    ?.addEventListener('mouseover', handleFilterMouseover);
+
+document.getElementById('record-input')
+    ?.addEventListener('input', showExplanation);
+
+// Tokens of the filter as last highlighted, updated by each edit instead of re-lexing the whole input
+let liveLexer = new wasm.IncrementalLexer('');
+
+// Fields the filter is type checked against, if the input gives any, e.g.
+// `data-schema='{"age": "number", "name": "string", "status": ["open", "closed"]}'`. A field can also be given as
+// `{"type": "number", "description": "..."}` to describe it when hovered.
+const schema = loadSchema(document.getElementById('filter-input')?.dataset.schema);
+
+function loadSchema(json: string | undefined): wasm.Schema | null {
+    if (!json)
+        return null;
+
+    try {
+        return wasm.Schema.from_json(json);
+    }
+    catch (err) {
+        console.error(err);
+        return null;
+    }
+}
+
+// Completions are still worth showing without a schema, just without field names
+const completionSchema = schema ?? new wasm.Schema();
+
 function handleFilterSubmit(e: SubmitEvent) {
     const start = performance.now();
     e.preventDefault();
@@ -32,7 +62,15 @@ function handleFilterSubmit(e: SubmitEvent) {
 }
 
 function handleFilterKeydown(e: KeyboardEvent) {
-    if (e.shiftKey && e.key === 'Enter') {
+    const completions = document.getElementById('filter-completions');
+    if (completions && !completions.classList.contains('d-none') && (e.key === 'Tab' || e.key === 'Escape')) {
+        e.preventDefault();
+        if (e.key === 'Tab')
+            (completions.firstElementChild as HTMLElement | null)?.click();
+        else
+            completions.classList.add('d-none');
+    }
+    else if (e.shiftKey && e.key === 'Enter') {
         e.preventDefault();
 
         const inputElement = document.getElementById('filter-input');
@@ -77,14 +115,25 @@ function handleFilterInput(e: Event) {
     const position = saveCursorPosition(inputElement);
     const input = inputElement.textContent ?? '';
 
-    // Tokenize input
-    const output = wasm.lex_filter(input);
+    // Tokenize input, re-lexing only the part that changed since the last input event
+    const edit = findEdit(liveLexer.text, input);
+    try {
+        liveLexer.edit(edit.offset, edit.deleted, edit.inserted);
+    }
+    catch {
+        liveLexer = new wasm.IncrementalLexer(input);
+    }
+    const output = { tokens: liveLexer.tokens, errors: liveLexer.errors };
+
+    // Type check the filter once it lexes
+    const typeErrors = schema && !output.errors.length ? wasm.check_filter(input, schema) : [];
+    output.errors.push(...typeErrors);
 
     if (output.errors.length) {
         // Display the error message to the user
         const filter_error = document.getElementById('filter-error');
         if (filter_error) {
-            filter_error.textContent = output.errors.map((err: wasm.FilterError) => err.message).join('\n');
+            filter_error.replaceChildren(...output.errors.map((err: wasm.FilterError) => renderError(inputElement, input, err)));
             filter_error.classList.remove('d-none');
         }
     }
@@ -92,7 +141,7 @@ function handleFilterInput(e: Event) {
         // Ensure no error message is present
         const filter_error = document.getElementById('filter-error');
         if (filter_error) {
-            filter_error.textContent = '';
+            filter_error.replaceChildren();
             filter_error.classList.add('d-none');
         }
     }
@@ -123,15 +172,18 @@ function handleFilterInput(e: Event) {
                 className = 'hl-invalid';
                 break;
         }
+        if (typeErrors.some((err: wasm.FilterError) => err.span.start.utf16 <= token.span.start.utf16 && token.span.end.utf16 <= err.span.end.utf16))
+            className = 'hl-invalid';
 
         // Append non-highlighted text
-        const textSegment = document.createTextNode(input.slice(last_end, token.start));
-        last_end = token.end;
+        const textSegment = document.createTextNode(input.slice(last_end, token.span.start.utf16));
+        last_end = token.span.end.utf16;
 
         // Append highlighted text
         const highlightedSegment = document.createElement('span');
         highlightedSegment.className = className;
-        highlightedSegment.textContent = input.slice(token.start, token.end);
+        highlightedSegment.dataset.start = String(token.span.start.utf16);
+        highlightedSegment.textContent = input.slice(token.span.start.utf16, token.span.end.utf16);
 
         wrapper.append(textSegment, highlightedSegment);
     }
@@ -143,6 +195,156 @@ function handleFilterInput(e: Event) {
     inputElement.innerHTML = wrapper.innerHTML;
     restoreCursorPosition(inputElement, position);
 
+    showCompletions(inputElement, input, position);
+    showExplanation();
+
     const end = performance.now();
     console.log(`Parsing took ${end - start} ms`);
+}
+
+// How the filter was evaluated against a record, from `wasm.explain_filter`
+interface Explanation {
+    type: 'comparison' | 'search';
+    result: boolean;
+    span: { start: number, end: number };   // In UTF-16 code units
+    // Comparisons
+    actual?: number | string | null;
+    // Searches
+    join_type?: 'and' | 'or' | 'xor';
+    children?: Explanation[];
+    deciding?: number[];
+}
+
+// Shows which parts of the filter hold for the record typed below it, in green, and which don't, in red. Parts that
+// didn't decide the result are faded.
+function showExplanation() {
+    const list = document.getElementById('explanation');
+    const filter = document.getElementById('filter-input')?.textContent ?? '';
+    const record = (document.getElementById('record-input') as HTMLTextAreaElement | null)?.value ?? '';
+    if (!list) return;
+
+    if (!filter.trim() || !record.trim()) {
+        list.replaceChildren();
+        return;
+    }
+
+    try {
+        list.replaceChildren(renderExplanation(filter, JSON.parse(wasm.explain_filter(filter, record)), true));
+    }
+    catch (err) {
+        // The filter's errors are already shown, but the record's aren't
+        const item = document.createElement('li');
+        item.className = 'text-warning';
+        item.textContent = String(err);
+        list.replaceChildren(item);
+    }
+}
+
+function renderExplanation(filter: string, explanation: Explanation, deciding: boolean): HTMLElement {
+    const item = document.createElement('li');
+    item.className = explanation.result ? 'text-success' : 'text-danger';
+    if (!deciding)
+        item.classList.add('opacity-50');
+
+    if (explanation.type === 'comparison') {
+        const actual = explanation.actual == null ? 'missing' : JSON.stringify(explanation.actual);
+        item.textContent = `${filter.slice(explanation.span.start, explanation.span.end)} (was ${actual})`;
+        return item;
+    }
+
+    const labels = { and: 'All of', or: 'Any of', xor: 'An odd number of' };
+    item.textContent = `${labels[explanation.join_type ?? 'and']}:`;
+    const children = document.createElement('ul');
+    explanation.children?.forEach((child, i) =>
+        children.append(renderExplanation(filter, child, explanation.deciding?.includes(i) ?? true)));
+    item.append(children);
+    return item;
+}
+
+// Shows what a token means as its tooltip, worked out when it's first hovered
+function handleFilterMouseover(e: MouseEvent) {
+    const target = e.target as HTMLElement;
+    const input = document.getElementById('filter-input')?.textContent;
+    if (target.dataset.start === undefined || target.title || input == null)
+        return;
+
+    target.title = wasm.hover(input, Number(target.dataset.start), completionSchema)?.contents ?? '';
+}
+
+// An error message, followed by a button for each of its quick fixes
+function renderError(inputElement: HTMLElement, input: string, err: wasm.FilterError): HTMLElement {
+    const line = document.createElement('div');
+    line.textContent = err.message;
+
+    for (const fix of err.fixes) {
+        const button = document.createElement('button');
+        button.type = 'button';
+        button.className = 'btn btn-sm btn-outline-light ms-2';
+        button.textContent = fix.title;
+        button.addEventListener('click', () => {
+            const start = fix.span.start.utf16;
+            inputElement.textContent = input.slice(0, start) + fix.replacement + input.slice(fix.span.end.utf16);
+            inputElement.focus();
+            restoreCursorPosition(inputElement, start + fix.replacement.length);
+            inputElement.dispatchEvent(new Event('input'));
+        });
+        line.append(button);
+    }
+
+    return line;
+}
+
+// Lists what could be typed at the cursor below the input. Clicking one (or pressing Tab for the first) applies it.
+function showCompletions(inputElement: HTMLElement, input: string, position: number | null) {
+    const list = document.getElementById('filter-completions');
+    if (!list)
+        return;
+
+    const completions = position === null ? [] : wasm.complete(input, position, completionSchema);
+    list.replaceChildren(...completions.map((completion: wasm.Completion) => {
+        const item = document.createElement('button');
+        item.type = 'button';
+        item.className = 'list-group-item list-group-item-action';
+        item.textContent = completion.replacement;
+
+        const detail = document.createElement('small');
+        detail.className = 'text-body-secondary ms-2';
+        detail.textContent = completion.detail;
+        item.append(detail);
+
+        item.addEventListener('click', () => {
+            const start = completion.span.start.utf16;
+            inputElement.textContent = input.slice(0, start) + completion.replacement + input.slice(completion.span.end.utf16);
+            inputElement.focus();
+            restoreCursorPosition(inputElement, start + completion.replacement.length);
+            inputElement.dispatchEvent(new Event('input'));
+        });
+        return item;
+    }));
+    list.classList.toggle('d-none', !completions.length);
+}
+
+// The smallest single replacement that turns `before` into `after`, in UTF-16 code units
+function findEdit(before: string, after: string) {
+    let prefix = 0;
+    const maxPrefix = Math.min(before.length, after.length);
+    while (prefix < maxPrefix && before[prefix] === after[prefix])
+        prefix++;
+
+    let suffix = 0;
+    const maxSuffix = maxPrefix - prefix;
+    while (suffix < maxSuffix && before[before.length - 1 - suffix] === after[after.length - 1 - suffix])
+        suffix++;
+
+    // Don't split a surrogate pair
+    if (prefix > 0 && /[\uD800-\uDBFF]/.test(before[prefix - 1]))
+        prefix--;
+    if (suffix > 0 && /[\uDC00-\uDFFF]/.test(after[after.length - suffix]))
+        suffix--;
+
+    return {
+        offset: prefix,
+        deleted: before.length - prefix - suffix,
+        inserted: after.slice(prefix, after.length - suffix)
+    };
 }
\ No newline at end of file